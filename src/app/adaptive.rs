@@ -0,0 +1,171 @@
+use lle::num_complex::Complex64;
+use ui_traits::ControllerUI;
+
+use crate::controller::{Components, Controller, Simulator, StoreState};
+
+use super::Core;
+
+/// Bounds of the change of `Δt` from one batch to the next
+const MIN_FACTOR: f64 = 0.2;
+const MAX_FACTOR: f64 = 2.;
+/// Margin on the optimal `Δt`
+const SAFETY: f64 = 0.9;
+/// Error estimates before running a batch anyway
+const MAX_TRIES: usize = 8;
+
+/// Adapts `Δt` once per batch, before running it, to keep the local error under `tolerance`.
+///
+/// The error is estimated by step doubling, on a trial copy of the simulator: one step of `Δt`
+/// against two steps of `Δt/2`. The split-step scheme being of second order, the local error
+/// scales as `Δt³`. The whole batch then runs with the same `Δt`, the error within a batch
+/// isn't controlled. The step count no longer maps to a time linearly.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub(crate) struct AdaptiveStep {
+    enabled: bool,
+    /// relative local error per step
+    tolerance: f64,
+    /// `Δt` of the controller it's adapted from, the one of the last batch and the next to try
+    #[serde(skip)]
+    step_dist: Option<(f64, f64, f64)>,
+}
+
+impl Default for AdaptiveStep {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tolerance: 1E-6,
+            step_dist: None,
+        }
+    }
+}
+
+/// `‖a - b‖ / ‖b‖` over all the components
+fn relative_error(a: &[&mut [Complex64]], b: &[&mut [Complex64]]) -> f64 {
+    let (diff, norm) = a
+        .iter()
+        .zip(b)
+        .flat_map(|(a, b)| a.iter().zip(b.iter()))
+        .fold((0., 0.), |(d, n), (a, b)| {
+            (d + (a - b).norm_sqr(), n + b.norm_sqr())
+        });
+    if norm > 0. {
+        (diff / norm).sqrt()
+    } else {
+        diff.sqrt()
+    }
+}
+
+/// `Δt` giving an error of about `tolerance`, from `error` at `step_dist`
+fn next_step_dist(step_dist: f64, error: f64, tolerance: f64) -> f64 {
+    let factor = if error > 0. {
+        SAFETY * (tolerance / error).cbrt()
+    } else {
+        MAX_FACTOR
+    };
+    step_dist * factor.clamp(MIN_FACTOR, MAX_FACTOR)
+}
+
+impl AdaptiveStep {
+    /// Effective `Δt` of the last batch, if adapted
+    pub(crate) fn step_dist(&self) -> Option<f64> {
+        self.step_dist.filter(|_| self.enabled).map(|(_, h, _)| h)
+    }
+
+    /// Keep the `Δt` adapted by a batch run elsewhere
+    pub(crate) fn update(&mut self, other: &Self) {
+        self.step_dist = other.step_dist;
+    }
+
+    /// Run `steps` steps on `core`, with the parameters already synchronized.
+    /// `trial` is the copy the error is estimated on, kept from batch to batch for its FFT plans
+    pub(crate) fn run<C: Controller<S>, S: Simulator>(
+        &mut self,
+        core: &mut Core<C, S>,
+        trial: &mut Option<S>,
+        steps: u32,
+    ) {
+        if !self.enabled {
+            core.simulator.run(steps);
+            return;
+        }
+        let Core {
+            dim,
+            controller,
+            simulator,
+            ..
+        } = core;
+        let base = simulator.step_dist();
+        // restart from the controller when it's edited
+        let mut h = match self.step_dist {
+            Some((b, _, next)) if b == base => next,
+            _ => base,
+        };
+        let trial = trial.get_or_insert_with(|| controller.construct_engine(*dim));
+        controller.sync_paras(trial);
+        let state = simulator.get_owned_state();
+        let mut attempt = |h: f64, n: u32| {
+            // every attempt starts from where the main simulator stands
+            controller.sync_history(simulator, trial);
+            trial.set_owned_state(state.clone());
+            trial.set_step_dist(h);
+            trial.run(n);
+            trial.get_owned_state()
+        };
+        let mut next = h;
+        for _ in 0..MAX_TRIES {
+            let (mut one, mut two) = (attempt(h, 1), attempt(h / 2., 2));
+            let error = relative_error(&one.components_mut(), &two.components_mut());
+            next = next_step_dist(h, error, self.tolerance);
+            if error <= self.tolerance {
+                // the next batch tries the grown step first
+                break;
+            }
+            h = next;
+            next = h;
+        }
+        simulator.set_step_dist(h);
+        simulator.run(steps);
+        self.step_dist = Some((base, h, next));
+    }
+}
+
+impl ControllerUI for AdaptiveStep {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.enabled, "Adaptive Δt").on_hover_text(
+                "Adapt the step before every batch to keep the local error, estimated by\n\
+                step doubling, under the tolerance, starting from the Δt of the model",
+            );
+            if self.enabled {
+                ui.add(
+                    egui::DragValue::new(&mut self.tolerance)
+                        .speed(1E-7)
+                        .range(1E-12..=1E-1)
+                        .prefix("tol ")
+                        .custom_formatter(|x, _| format!("{x:.1E}")),
+                );
+                if let Some(h) = self.step_dist() {
+                    ui.label(format!("Δt = {h:.3E}"));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn step_control() {
+        let mut a = vec![Complex64::new(1., 0.); 4];
+        let mut b = vec![Complex64::new(1., 1E-3); 4];
+        assert!((relative_error(&[&mut a[..]], &[&mut b[..]]) - 1E-3).abs() < 1E-9);
+
+        // the error scales as Δt³
+        let h = next_step_dist(1., 8E-6, 1E-6);
+        assert!((h - SAFETY * 0.5).abs() < 1E-12);
+        assert_eq!(next_step_dist(1., 0., 1E-6), MAX_FACTOR);
+        assert_eq!(next_step_dist(1., 1., 1E-6), MIN_FACTOR);
+    }
+}
@@ -0,0 +1,309 @@
+use std::f64::consts::TAU;
+
+use ui_traits::{ControllerUI, DisplayStr, Params};
+
+/// Schedules of the parameters over the solver steps, applied before every batch
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Automation {
+    schedules: Vec<Scheduled>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Scheduled {
+    /// path of the parameter, see [`Params`]
+    path: String,
+    enabled: bool,
+    schedule: Schedule,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum Schedule {
+    /// Linear from `from` at step `start` to `to` at step `end`, constant outside
+    Ramp {
+        from: f64,
+        to: f64,
+        start: u32,
+        end: u32,
+    },
+    /// Piecewise linear between the points, constant outside
+    Table(Vec<Point>),
+    Sine {
+        offset: f64,
+        amplitude: f64,
+        /// in steps
+        period: u32,
+        /// in rad
+        phase: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Point {
+    step: u32,
+    value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_iterator::Sequence)]
+enum ScheduleKind {
+    Ramp,
+    Table,
+    Sine,
+}
+
+impl DisplayStr for ScheduleKind {
+    fn desc(&self) -> &str {
+        match self {
+            ScheduleKind::Ramp => "Ramp",
+            ScheduleKind::Table => "Table",
+            ScheduleKind::Sine => "Sine",
+        }
+    }
+}
+
+impl Schedule {
+    /// Schedule of `kind` holding `value` for now
+    fn new(kind: ScheduleKind, value: f64) -> Self {
+        match kind {
+            ScheduleKind::Ramp => Schedule::Ramp {
+                from: value,
+                to: value,
+                start: 0,
+                end: 100_000,
+            },
+            ScheduleKind::Table => Schedule::Table(vec![Point { step: 0, value }]),
+            ScheduleKind::Sine => Schedule::Sine {
+                offset: value,
+                amplitude: 0.,
+                period: 100_000,
+                phase: 0.,
+            },
+        }
+    }
+
+    fn kind(&self) -> ScheduleKind {
+        match self {
+            Schedule::Ramp { .. } => ScheduleKind::Ramp,
+            Schedule::Table(_) => ScheduleKind::Table,
+            Schedule::Sine { .. } => ScheduleKind::Sine,
+        }
+    }
+
+    /// Value at `step`, `None` for an empty table
+    pub(crate) fn value(&self, step: u32) -> Option<f64> {
+        match *self {
+            Schedule::Ramp {
+                from,
+                to,
+                start,
+                end,
+            } => {
+                if step <= start {
+                    Some(from)
+                } else if step >= end {
+                    Some(to)
+                } else {
+                    let t = (step - start) as f64 / (end - start) as f64;
+                    Some(from + (to - from) * t)
+                }
+            }
+            Schedule::Table(ref points) => {
+                let before = points
+                    .iter()
+                    .filter(|p| p.step <= step)
+                    .max_by_key(|p| p.step);
+                let after = points
+                    .iter()
+                    .filter(|p| p.step > step)
+                    .min_by_key(|p| p.step);
+                match (before, after) {
+                    (Some(b), Some(a)) => {
+                        let t = (step - b.step) as f64 / (a.step - b.step) as f64;
+                        Some(b.value + (a.value - b.value) * t)
+                    }
+                    (Some(p), None) | (None, Some(p)) => Some(p.value),
+                    (None, None) => None,
+                }
+            }
+            Schedule::Sine {
+                offset,
+                amplitude,
+                period,
+                phase,
+            } => {
+                if period == 0 {
+                    return Some(offset);
+                }
+                let cycle = (step % period) as f64 / period as f64;
+                Some(offset + amplitude * (TAU * cycle + phase).sin())
+            }
+        }
+    }
+}
+
+impl ControllerUI for Point {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::DragValue::new(&mut self.step).prefix("step "));
+        ui.add(egui::DragValue::new(&mut self.value).speed(1E-2));
+    }
+}
+
+impl ControllerUI for Schedule {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        match self {
+            Schedule::Ramp {
+                from,
+                to,
+                start,
+                end,
+            } => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(from).speed(1E-2).prefix("from "));
+                    ui.add(egui::DragValue::new(to).speed(1E-2).prefix("to "));
+                });
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(start).prefix("step "));
+                    ui.add(egui::DragValue::new(end).prefix("to step "));
+                });
+            }
+            Schedule::Table(points) => crate::util::show_vector(ui, points),
+            Schedule::Sine {
+                offset,
+                amplitude,
+                period,
+                phase,
+            } => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(offset).speed(1E-2).prefix("offset "));
+                    ui.add(egui::DragValue::new(amplitude).speed(1E-2).prefix("amp "));
+                });
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(period)
+                            .range(1..=u32::MAX)
+                            .prefix("period "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(phase)
+                            .speed(1E-2)
+                            .prefix("phase ")
+                            .suffix(" rad"),
+                    );
+                });
+            }
+        }
+    }
+}
+
+impl Automation {
+    /// Write the scheduled values at `step` to `params`, marking them as driven.
+    /// The driven marks are expected to be cleared before.
+    pub(crate) fn apply(&self, params: &mut dyn Params, step: u32) {
+        if self.schedules.is_empty() {
+            return;
+        }
+        params.visit_params("", &mut |path, param| {
+            let value = self
+                .schedules
+                .iter()
+                .filter(|s| s.enabled && s.path == path)
+                .find_map(|s| Some((s.schedule.kind(), s.schedule.value(step)?)));
+            if let Some((kind, value)) = value {
+                param.set_f64(value);
+                param.set_driven(Some(format!("Driven by the {} schedule", kind.desc())));
+            }
+        });
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, params: &mut dyn Params, step: u32) {
+        ui.collapsing("Parameter automation", |ui| {
+            let paths = params.param_paths();
+            let mut to_remove = None;
+            for (i, s) in self.schedules.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut s.enabled, "");
+                        egui::ComboBox::from_id_salt("parameter")
+                            .selected_text(s.path.as_str())
+                            .show_ui(ui, |ui| {
+                                for p in &paths {
+                                    ui.selectable_value(&mut s.path, p.clone(), p);
+                                }
+                            });
+                        let mut kind = s.schedule.kind();
+                        egui::ComboBox::from_id_salt("kind")
+                            .selected_text(kind.desc())
+                            .show_ui(ui, |ui| kind.show_controller(ui));
+                        if kind != s.schedule.kind() {
+                            let value = s
+                                .schedule
+                                .value(step)
+                                .or_else(|| params.get_param(&s.path))
+                                .unwrap_or_default();
+                            s.schedule = Schedule::new(kind, value);
+                        }
+                        if ui.button("🗑").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                    s.schedule.show_controller(ui);
+                    if !paths.contains(&s.path) {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Unknown parameter");
+                    }
+                });
+                ui.separator();
+            }
+            if let Some(i) = to_remove {
+                self.schedules.remove(i);
+            }
+            if ui.button("➕").on_hover_text("Add a schedule").clicked()
+                && let Some(path) = paths.first()
+            {
+                let value = params.get_param(path).unwrap_or_default();
+                self.schedules.push(Scheduled {
+                    path: path.clone(),
+                    enabled: true,
+                    schedule: Schedule::new(ScheduleKind::Ramp, value),
+                });
+            }
+            ui.label(format!("Current step: {step}"));
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn schedule_values() {
+        let ramp = Schedule::Ramp {
+            from: 1.,
+            to: 3.,
+            start: 100,
+            end: 200,
+        };
+        assert_eq!(ramp.value(0), Some(1.));
+        assert_eq!(ramp.value(150), Some(2.));
+        assert_eq!(ramp.value(1000), Some(3.));
+
+        let table = Schedule::Table(vec![
+            Point {
+                step: 100,
+                value: 2.,
+            },
+            Point { step: 0, value: 0. },
+        ]);
+        assert_eq!(table.value(50), Some(1.));
+        assert_eq!(table.value(500), Some(2.));
+        assert_eq!(Schedule::Table(vec![]).value(0), None);
+
+        let sine = Schedule::Sine {
+            offset: 1.,
+            amplitude: 2.,
+            period: 400,
+            phase: 0.,
+        };
+        assert!((sine.value(100).unwrap() - 3.).abs() < 1E-12);
+        assert!((sine.value(500).unwrap() - 3.).abs() < 1E-12);
+    }
+}
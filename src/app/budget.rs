@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use ui_traits::{ControllerUI, DisplayStr};
+
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Upper bound of the solver time of one batch, so a too high target can't freeze the app
+const MAX_BATCH_TIME: f64 = 0.1;
+/// Lower bound of the measured solver time of a step, in s, for batches too fast to be timed
+const MIN_STEP_COST: f64 = 1E-8;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    enum_iterator::Sequence,
+)]
+pub(crate) enum BudgetMode {
+    /// `steps` of the controller
+    #[default]
+    Fixed,
+    /// Solver time of every batch
+    FrameTime,
+    /// Simulated steps per second
+    Rate,
+}
+
+impl DisplayStr for BudgetMode {
+    fn desc(&self) -> &str {
+        match self {
+            BudgetMode::Fixed => "Fixed",
+            BudgetMode::FrameTime => "Frame time",
+            BudgetMode::Rate => "Steps/s",
+        }
+    }
+}
+
+/// Chooses the number of steps of every batch from the measured solver time
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct StepBudget {
+    mode: BudgetMode,
+    /// target solver time of a batch, in ms
+    frame_time: f64,
+    /// target steps per second
+    rate: f64,
+    /// smoothed solver time of a single step, in s
+    #[serde(skip)]
+    cost: Option<f64>,
+    /// smoothed time between two batches, in s
+    #[serde(skip)]
+    interval: Option<f64>,
+    #[serde(skip)]
+    last_batch: Option<Instant>,
+    #[serde(skip)]
+    last_steps: Option<u32>,
+}
+
+impl Default for StepBudget {
+    fn default() -> Self {
+        Self {
+            mode: BudgetMode::Fixed,
+            frame_time: 10.,
+            rate: 1E4,
+            cost: None,
+            interval: None,
+            last_batch: None,
+            last_steps: None,
+        }
+    }
+}
+
+fn smooth(old: Option<f64>, new: f64) -> f64 {
+    const WEIGHT: f64 = 0.2;
+    old.map_or(new, |old| old * (1. - WEIGHT) + new * WEIGHT)
+}
+
+impl StepBudget {
+    /// Steps of the next batch, `fixed` being the `steps` of the controller
+    pub(crate) fn steps(&mut self, fixed: u32) -> u32 {
+        let steps = match (self.mode, self.cost.map(|c| c.max(MIN_STEP_COST))) {
+            (BudgetMode::Fixed, _) | (_, None) => fixed,
+            (BudgetMode::FrameTime, Some(cost)) => {
+                ((self.frame_time * 1E-3 / cost) as u32).min((MAX_BATCH_TIME / cost) as u32)
+            }
+            (BudgetMode::Rate, Some(cost)) => {
+                let interval = self.interval.unwrap_or(1. / 60.);
+                ((self.rate * interval) as u32).min((MAX_BATCH_TIME / cost) as u32)
+            }
+        }
+        .max(1);
+        self.last_steps = Some(steps);
+        steps
+    }
+
+    /// Record a finished batch of `steps` steps, solved in `elapsed`
+    pub(crate) fn record(&mut self, steps: u32, elapsed: Duration) {
+        if steps != 0 {
+            self.cost = Some(smooth(self.cost, elapsed.as_secs_f64() / steps as f64));
+        }
+        let now = Instant::now();
+        if let Some(last) = self.last_batch.replace(now) {
+            self.interval = Some(smooth(self.interval, (now - last).as_secs_f64()));
+        }
+    }
+
+    /// Forget the measured timings, when the simulation is paused
+    pub(crate) fn pause(&mut self) {
+        self.last_batch = None;
+        self.interval = None;
+    }
+}
+
+impl ControllerUI for StepBudget {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Steps per batch").on_hover_text(
+                "Fixed uses the steps of the model,\nthe others adapt to the measured solver time",
+            );
+            self.mode.show_controller(ui);
+        });
+        match self.mode {
+            BudgetMode::Fixed => {}
+            BudgetMode::FrameTime => {
+                ui.add(
+                    egui::Slider::new(&mut self.frame_time, 1.0..=100.0)
+                        .logarithmic(true)
+                        .suffix(" ms")
+                        .text("Target solver time"),
+                );
+            }
+            BudgetMode::Rate => {
+                ui.add(
+                    egui::Slider::new(&mut self.rate, 1E2..=1E7)
+                        .logarithmic(true)
+                        .clamping(egui::SliderClamping::Never)
+                        .text("Target steps/s"),
+                );
+            }
+        }
+        if let Some(steps) = self.last_steps {
+            let rate = self.interval.map_or(String::new(), |i| {
+                format!(", {:.3E} steps/s", steps as f64 / i)
+            });
+            ui.label(format!("{steps} steps/batch{rate}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bounded_steps() {
+        let mut budget = StepBudget {
+            mode: BudgetMode::FrameTime,
+            frame_time: 100.,
+            ..Default::default()
+        };
+        assert_eq!(budget.steps(100), 100);
+        budget.record(100, Duration::ZERO);
+        let max = (MAX_BATCH_TIME / MIN_STEP_COST) as u32;
+        assert_eq!(budget.steps(100), max);
+        budget.mode = BudgetMode::Rate;
+        budget.rate = f64::INFINITY;
+        assert_eq!(budget.steps(100), max);
+        budget.record(1, Duration::from_secs(1));
+        assert_eq!(budget.steps(100), 1);
+    }
+}
@@ -0,0 +1,274 @@
+use lle::num_complex::Complex64;
+
+use crate::controller::{Components, Controller, Simulator, StoreState};
+
+use super::Core;
+
+/// Figures of merit of the comb of one field, the powers in the units of `|ψ|²`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Metrics {
+    /// power of every line, by mode number
+    lines: Vec<(i32, f64)>,
+    pub(crate) total: f64,
+    /// `None` for a field without pump
+    pub(crate) pump_mode: Option<i32>,
+    pub(crate) pump: f64,
+    /// power of all the other lines
+    pub(crate) sidebands: f64,
+    /// power-weighted mean mode of the sidebands relative to the pump, the soliton recoil
+    pub(crate) centroid: f64,
+}
+
+/// Line of a field driven by the pump
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PumpLine {
+    Mode(i32),
+    /// for a pump the model doesn't tell, the strongest line
+    Strongest,
+    None,
+}
+
+impl Metrics {
+    /// From the unnormalized spectrum of a field, in the fft order
+    pub(crate) fn new(spectrum: &[Complex64], pump: PumpLine) -> Self {
+        let len = spectrum.len();
+        let mut lines = spectrum
+            .iter()
+            .enumerate()
+            .map(|(k, x)| {
+                let mode = if k < len.div_ceil(2) {
+                    k as i32
+                } else {
+                    k as i32 - len as i32
+                };
+                (mode, (x / len as f64).norm_sqr())
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|l| l.0);
+        let total = lines.iter().map(|l| l.1).sum::<f64>();
+        let pump_mode = match pump {
+            PumpLine::Mode(m) => Some(m),
+            PumpLine::Strongest => lines.iter().max_by(|a, b| a.1.total_cmp(&b.1)).map(|l| l.0),
+            PumpLine::None => None,
+        };
+        let pump = lines
+            .iter()
+            .find(|l| Some(l.0) == pump_mode)
+            .map_or(0., |l| l.1);
+        let sidebands = total - pump;
+        let centroid = if sidebands > 0. {
+            let center = pump_mode.unwrap_or_default();
+            lines
+                .iter()
+                .filter(|l| Some(l.0) != pump_mode)
+                .map(|&(m, p)| (m - center) as f64 * p)
+                .sum::<f64>()
+                / sidebands
+        } else {
+            0.
+        };
+        Self {
+            lines,
+            total,
+            pump_mode,
+            pump,
+            sidebands,
+            centroid,
+        }
+    }
+
+    /// Span in modes of the sidebands within `db` of the strongest one
+    pub(crate) fn bandwidth(&self, db: f64) -> i32 {
+        let side = || self.lines.iter().filter(|l| Some(l.0) != self.pump_mode);
+        let max = side().map(|l| l.1).fold(0., f64::max);
+        if max <= 0. {
+            return 0;
+        }
+        let floor = max * 10f64.powf(-db / 10.);
+        let mut modes = side().filter(|l| l.1 >= floor).map(|l| l.0);
+        let first = modes.next().unwrap_or_default();
+        modes.next_back().unwrap_or(first) - first
+    }
+
+    /// Sideband power over the pump power `f²`, for a critically coupled resonator
+    pub(crate) fn efficiency(&self, f: f64) -> f64 {
+        self.sidebands / (f * f)
+    }
+}
+
+/// Comb figures of merit of every field of the current state
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct CombMetrics {
+    pub(crate) open: bool,
+    #[serde(skip)]
+    fft: Option<(usize, (lle::BufferedFft<f64>, lle::BufferedFft<f64>))>,
+}
+
+impl CombMetrics {
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            open: self.open,
+            fft: None,
+        }
+    }
+
+    fn measure<P: Controller<S>, S: Simulator>(&mut self, core: &Core<P, S>) -> Vec<Metrics> {
+        let pump = core.controller.cw_pump();
+        let mut state = core.simulator.get_owned_state();
+        state
+            .components_mut()
+            .into_iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let n = f.len();
+                let fft = match &mut self.fft {
+                    Some((len, fft)) if *len == n => fft,
+                    fft => &mut fft.insert((n, lle::BufferedFft::new(n))).1,
+                };
+                let mut f = f.to_vec();
+                fft.0.fft_process(&mut f);
+                let line = match pump {
+                    Some(p) if p.field == i => PumpLine::Mode(p.mode),
+                    Some(_) => PumpLine::None,
+                    None => PumpLine::Strongest,
+                };
+                Metrics::new(&f, line)
+            })
+            .collect()
+    }
+
+    pub(crate) fn show<P, S>(&mut self, ctx: &egui::Context, core: &Core<P, S>)
+    where
+        P: Controller<S> + Clone,
+        S: Simulator,
+    {
+        if !self.open {
+            return;
+        }
+        let metrics = self.measure(core);
+        let pump = core.controller.cw_pump().map(|p| p.amplitude);
+        egui::Window::new("Comb metrics")
+            .open(&mut self.open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("comb metrics")
+                    .striped(true)
+                    .show(ui, |ui| show_metrics(ui, &metrics, pump));
+                ui.collapsing("Lines", |ui| {
+                    egui::Grid::new("comb lines")
+                        .striped(true)
+                        .show(ui, |ui| show_lines(ui, &metrics));
+                });
+            });
+    }
+}
+
+fn db(x: f64) -> String {
+    format!("{:.2} dB", 10. * x.log10())
+}
+
+fn header(ui: &mut egui::Ui, first: &str, metrics: &[Metrics]) {
+    ui.label(first);
+    for i in 0..metrics.len() {
+        ui.strong(format!("Field {i}"));
+    }
+    ui.end_row();
+}
+
+/// One column per field, with the conversion efficiency when the model has a CW pump of amplitude `pump`
+fn show_metrics(ui: &mut egui::Ui, metrics: &[Metrics], pump: Option<f64>) {
+    let row = |ui: &mut egui::Ui, name: &str, f: &dyn Fn(&Metrics) -> String| {
+        ui.label(name);
+        for m in metrics {
+            ui.label(f(m));
+        }
+        ui.end_row();
+    };
+    if metrics.len() > 1 {
+        header(ui, "", metrics);
+    }
+    row(ui, "Comb power", &|m| format!("{:.4}", m.total));
+    row(ui, "Pump mode", &|m| {
+        m.pump_mode.map_or("-".to_string(), |p| p.to_string())
+    });
+    row(ui, "Pump power", &|m| format!("{:.4}", m.pump));
+    row(ui, "Sideband power", &|m| format!("{:.4}", m.sidebands));
+    row(ui, "Pump / sidebands", &|m| db(m.pump / m.sidebands));
+    row(ui, "3 dB bandwidth", &|m| {
+        format!("{} modes", m.bandwidth(3.))
+    });
+    row(ui, "20 dB bandwidth", &|m| {
+        format!("{} modes", m.bandwidth(20.))
+    });
+    row(ui, "Centroid offset", &|m| {
+        format!("{:.3} modes", m.centroid)
+    });
+    if let Some(f) = pump {
+        row(ui, "Conversion efficiency", &|m| match m.pump_mode {
+            Some(_) => format!("{:.2} %", 100. * m.efficiency(f)),
+            None => "-".to_string(),
+        });
+    }
+}
+
+/// Power of every line of every field
+fn show_lines(ui: &mut egui::Ui, metrics: &[Metrics]) {
+    header(ui, "Mode", metrics);
+    let modes = metrics.first().map_or(&[][..], |m| &m.lines[..]);
+    for (j, (mode, _)) in modes.iter().enumerate() {
+        ui.label(mode.to_string());
+        for m in metrics {
+            ui.label(m.lines.get(j).map_or(String::new(), |l| db(l.1)));
+        }
+        ui.end_row();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metrics() {
+        let len: i32 = 64;
+        // pump at mode 0 on sidebands falling by 10 dB per mode, shifted to the blue
+        let power = |m: i32| match m {
+            0 => 4.,
+            1..=3 => 10f64.powi(-m),
+            -2..=-1 => 10f64.powi(m - 1),
+            _ => 0.,
+        };
+        let spectrum = (0..len)
+            .map(|k| {
+                let m = if k < len / 2 { k } else { k - len };
+                Complex64::new(power(m).sqrt() * len as f64, 0.)
+            })
+            .collect::<Vec<_>>();
+        let m = Metrics::new(&spectrum, PumpLine::Strongest);
+        let close = |a: f64, b: f64| (a - b).abs() < 1E-12;
+
+        assert_eq!(m.pump_mode, Some(0));
+        assert_eq!(Metrics::new(&spectrum, PumpLine::Mode(0)), m);
+        assert!(close(m.pump, 4.));
+        let sidebands = 0.1 + 0.01 + 0.001 + 0.01 + 0.001;
+        assert!(close(m.sidebands, sidebands));
+        assert!(close(m.total, 4. + sidebands));
+        assert!(close(
+            m.centroid,
+            (0.1 + 0.02 + 0.003 - 0.01 - 0.002) / sidebands
+        ));
+        assert!(close(m.efficiency(2.), sidebands / 4.));
+        // +1 alone, then -1 to +2, then all the sidebands
+        assert_eq!(m.bandwidth(3.), 0);
+        assert_eq!(m.bandwidth(11.), 3);
+        assert_eq!(m.bandwidth(21.), 5);
+        let single = Metrics::new(&[Complex64::new(1., 0.); 1], PumpLine::Strongest);
+        assert_eq!(single.bandwidth(20.), 0);
+
+        // a field without pump is all sidebands, centered on the mode 0
+        let m = Metrics::new(&spectrum, PumpLine::None);
+        assert_eq!(m.pump_mode, None);
+        assert!(close(m.sidebands, m.total));
+        assert_eq!(m.bandwidth(3.), 0);
+    }
+}
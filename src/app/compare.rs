@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use egui::mutex::Mutex;
+use lle::num_complex::Complex64;
+
+use crate::{
+    controller::{Components, Controller, SharedState, Simulator, StoreState},
+    drawer::plot_item::Style,
+    file::FileManager,
+    notify::ResultExt,
+    util::{Promise, try_poll},
+    views::{RawPlotData, ShowOn, State, Visualizer},
+};
+
+use super::{Core, CoreStorage, convergence::align};
+
+/// A second core pinned next to the main one, drawn on the same charts.
+///
+/// When the main core runs in the background, so does the pinned one, on its own thread.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct Compare<P, S: Simulator> {
+    #[serde(skip)]
+    pinned: Option<Arc<Mutex<Core<P, S>>>>,
+    /// batch of the pinned core running in the background
+    #[serde(skip)]
+    promise: Option<Promise<Snapshot<S>>>,
+    /// the pinned core after its last batch, drawn while the next one runs
+    #[serde(skip)]
+    snapshot: Option<Snapshot<S>>,
+    /// steps missed while the pinned core was busy, and whether they added noise
+    #[serde(skip)]
+    behind: Option<(u32, bool)>,
+    /// forward and inverse transforms of the cross-correlation, with their length
+    #[serde(skip)]
+    fft: Option<(usize, (lle::BufferedFft<f64>, lle::BufferedFft<f64>))>,
+    /// step the pinned core along with the main one
+    run: bool,
+    show_pinned: bool,
+    /// difference from the main field, after aligning the pinned one
+    show_difference: bool,
+    #[serde(default = "default_file")]
+    file: FileManager,
+}
+
+fn default_file() -> FileManager {
+    FileManager::new("Pinned model")
+}
+
+struct Snapshot<S: StoreState> {
+    state: S::OwnedState,
+    step: u32,
+}
+
+impl<S: Simulator> Snapshot<S> {
+    fn of<P>(core: &Core<P, S>) -> Self {
+        Self {
+            state: core.simulator.get_owned_state(),
+            step: core.simulator.cur_step(),
+        }
+    }
+}
+
+impl<P, S: Simulator> Default for Compare<P, S> {
+    fn default() -> Self {
+        Self {
+            pinned: None,
+            promise: None,
+            snapshot: None,
+            behind: None,
+            fft: None,
+            run: true,
+            show_pinned: true,
+            show_difference: false,
+            file: default_file(),
+        }
+    }
+}
+
+/// `a - e^{iφ} b(θ - s)`, with the shift `s` and the global phase `φ` minimizing its norm,
+/// that is maximizing the cross-correlation of `a` and `b`, refined between the grid points
+/// as the drift of [`super::convergence`]. The shifted `b` is interpolated by `fft`
+fn aligned_difference(
+    a: &[Complex64],
+    b: &[Complex64],
+    fft: &mut (lle::BufferedFft<f64>, lle::BufferedFft<f64>),
+) -> Option<Vec<Complex64>> {
+    let n = a.len();
+    if n == 0 || n != b.len() {
+        return None;
+    }
+    let (mut fa, mut fb) = (a.to_vec(), b.to_vec());
+    fft.0.fft_process(&mut fa);
+    fft.0.fft_process(&mut fb);
+    let product = fa
+        .iter()
+        .zip(&fb)
+        .map(|(x, y)| x * y.conj())
+        .collect::<Vec<_>>();
+    let mut corr = product.clone();
+    fft.1.fft_process(&mut corr);
+    let (shift, corr) = align(&product, &corr)?;
+    let rot = if corr.norm() > 0. {
+        corr / corr.norm()
+    } else {
+        Complex64::new(1., 0.)
+    };
+    let mut diff = fa
+        .iter()
+        .zip(&fb)
+        .enumerate()
+        .map(|(k, (x, y))| {
+            let mu = lle::freq_at(n, k) as f64;
+            x - rot * y * Complex64::from_polar(1., -mu * shift)
+        })
+        .collect::<Vec<_>>();
+    fft.1.fft_process(&mut diff);
+    diff.iter_mut().for_each(|x| *x /= n as f64);
+    Some(diff)
+}
+
+impl<P, S> Compare<P, S>
+where
+    P: Controller<S> + Clone + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+{
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            pinned: None,
+            promise: None,
+            snapshot: None,
+            behind: None,
+            fft: None,
+            run: self.run,
+            show_pinned: self.show_pinned,
+            show_difference: self.show_difference,
+            file: self.file.clone_for_save(),
+        }
+    }
+
+    /// Pin `core` in place of the pinned one, dropping the batch it may be running
+    fn pin(&mut self, core: Core<P, S>) {
+        self.snapshot = Some(Snapshot::of(&core));
+        self.pinned = Some(Arc::new(Mutex::new(core)));
+        self.promise = None;
+        self.behind = None;
+    }
+
+    /// Keep the pinned core after its batch in the background, if it's finished
+    fn poll(&mut self) {
+        if let Some(snapshot) = try_poll(&mut self.promise) {
+            self.snapshot = Some(snapshot);
+        }
+    }
+
+    /// Run the pinned core for the batch of the main one, on a worker thread in the `background`.
+    /// A batch the busy worker can't take is added to the next one.
+    pub(crate) fn step(&mut self, steps: u32, add_rand: bool, background: bool) {
+        let Some(pinned) = self.pinned.clone().filter(|_| self.run) else {
+            return;
+        };
+        self.poll();
+        let (steps, add_rand) = match self.behind.take() {
+            Some((behind, noise)) => (behind.saturating_add(steps), noise || add_rand),
+            None => (steps, add_rand),
+        };
+        if background && self.promise.is_some() {
+            self.behind = Some((steps, add_rand));
+            return;
+        }
+        let task = move || {
+            let mut pinned = pinned.lock();
+            pinned.sync_paras();
+            if add_rand {
+                pinned.add_random();
+            }
+            pinned.simulator.run(steps);
+            Snapshot::of(&*pinned)
+        };
+        if !background {
+            self.snapshot = Some(task());
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.promise = Some(Promise::new_thread("pinned", task));
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.promise = Some(Promise::new_web("pinned", task));
+        }
+    }
+
+    pub(crate) fn push_to_views<'a, V>(
+        &mut self,
+        core: &'a Core<P, S>,
+        views: &mut V,
+        running: bool,
+    ) where
+        V: Visualizer<<S as SharedState<'a>>::SharedState>,
+        <S as SharedState<'a>>::SharedState: State<OwnedState = <S as StoreState>::OwnedState>,
+    {
+        self.poll();
+        let Some(snapshot) = self.snapshot.as_ref().filter(|_| self.pinned.is_some()) else {
+            return;
+        };
+        let mut other = snapshot.state.clone();
+        if self.show_difference {
+            let mut diff = core.simulator.get_owned_state();
+            let ffts = &mut self.fft;
+            let aligned = diff
+                .components_mut()
+                .into_iter()
+                .zip(other.components_mut())
+                .all(|(d, o)| {
+                    let n = d.len();
+                    let fft = match &mut *ffts {
+                        Some((len, fft)) if *len == n => fft,
+                        fft => &mut fft.insert((n, lle::BufferedFft::new(n))).1,
+                    };
+                    aligned_difference(d, o, fft)
+                        .map(|a| d.copy_from_slice(&a))
+                        .is_some()
+                });
+            if aligned {
+                views.push_elements_raw(
+                    RawPlotData {
+                        data: diff,
+                        x: None,
+                        width: 0.,
+                        style: Some(Style::default()),
+                        legend: Some("Difference".to_string()),
+                    },
+                    ShowOn::Both,
+                    running,
+                );
+            }
+        }
+        if self.show_pinned {
+            views.push_elements_raw(
+                RawPlotData {
+                    data: other,
+                    x: None,
+                    width: 0.,
+                    style: Some(Style::default()),
+                    legend: Some("Pinned".to_string()),
+                },
+                ShowOn::Both,
+                running,
+            );
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, core: &Core<P, S>) {
+        ui.collapsing("A/B compare", |ui| {
+            ui.horizontal(|ui| {
+                let text = if self.pinned.is_some() {
+                    "📌 Pin again"
+                } else {
+                    "📌 Pin a copy"
+                };
+                if ui
+                    .button(text)
+                    .on_hover_text("Pin a copy of the current simulation, to compare with")
+                    .clicked()
+                {
+                    self.pin(CoreStorage::from(core).into());
+                }
+                if self.pinned.is_some() && ui.button("🗙 Unpin").clicked() {
+                    self.pinned = None;
+                    self.promise = None;
+                    self.snapshot = None;
+                    self.behind = None;
+                }
+            });
+            let Some(pinned) = self.pinned.clone() else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.run, "Run along");
+                ui.checkbox(&mut self.show_pinned, "Show");
+                ui.checkbox(&mut self.show_difference, "Difference")
+                    .on_hover_text("Main minus pinned field, aligned in translation and phase");
+            });
+            if let Some(snapshot) = &self.snapshot {
+                ui.label(format!("Pinned step: {}", snapshot.step));
+            }
+            if self.promise.is_some() {
+                // the worker holds the pinned core
+                ui.spinner();
+                return;
+            }
+            let mut pinned = pinned.lock();
+            if self.file.show_save_load(ui, &mut *pinned).notify_global() == Some(true) {
+                self.snapshot = Some(Snapshot::of(&*pinned));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn align_translation_and_phase() {
+        let n = 64;
+        let a = (0..n)
+            .map(|j| {
+                let t = 2. * PI * j as f64 / n as f64 - PI;
+                Complex64::from_polar(1. / (3. * t).cosh(), t)
+            })
+            .collect::<Vec<_>>();
+        let rot = Complex64::from_polar(1., 0.7);
+        let b = (0..n).map(|j| rot * a[(j + 5) % n]).collect::<Vec<_>>();
+        let mut fft = lle::BufferedFft::new(n);
+        let diff = aligned_difference(&a, &b, &mut fft).unwrap();
+        assert!(diff.iter().all(|d| d.norm() < 1E-12));
+
+        assert!(aligned_difference(&a, &b[1..], &mut fft).is_none());
+    }
+
+    #[test]
+    fn align_fractional_shift() {
+        let n = 128;
+        let soliton = |shift: f64, phase: f64| {
+            (0..n)
+                .map(|j| {
+                    let t = 2. * PI * j as f64 / n as f64 - PI;
+                    Complex64::from_polar(1. / (4. * (t - shift)).cosh(), phase)
+                })
+                .collect::<Vec<_>>()
+        };
+        // a third of the grid spacing
+        let a = soliton(0., 0.);
+        let b = soliton(2. * PI / n as f64 / 3., -1.2);
+        let mut fft = lle::BufferedFft::new(n);
+        let diff = aligned_difference(&a, &b, &mut fft).unwrap();
+        let norm = a.iter().map(|x| x.norm_sqr()).sum::<f64>();
+        let left = diff.iter().map(|x| x.norm_sqr()).sum::<f64>();
+        assert!(left < 1E-10 * norm);
+    }
+}
@@ -0,0 +1,321 @@
+use std::{collections::VecDeque, f64::consts::TAU};
+
+use lle::num_complex::Complex64;
+use ui_traits::DisplayStr;
+
+use crate::controller::{Components, Simulator, StoreState};
+
+use super::Core;
+
+/// Changes kept to tell an oscillation from a convergence
+const WINDOW: usize = 16;
+/// Newton iterations refining the drift
+const NEWTON: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Regime {
+    Stationary,
+    /// stationary in a moving frame
+    Drifting,
+    /// changing without settling
+    Oscillating,
+    /// changing less and less
+    Converging,
+}
+
+impl DisplayStr for Regime {
+    fn desc(&self) -> &str {
+        match self {
+            Regime::Stationary => "Stationary",
+            Regime::Drifting => "Drifting",
+            Regime::Oscillating => "Oscillating",
+            Regime::Converging => "Converging",
+        }
+    }
+}
+
+/// Change of the field over the last batch, per step and relative to `‖ψ‖`
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    /// with the drift and the global phase rotation removed
+    change: f64,
+    raw: f64,
+    /// shift of the field in θ, in rad per step
+    drift: f64,
+    regime: Option<Regime>,
+}
+
+/// Tracks how far the field is from a steady state, from one batch to the next
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct Convergence {
+    /// change per step under which the field is taken as stationary
+    tolerance: f64,
+    /// spectra of the components after the last batch, with its step
+    #[serde(skip)]
+    last: Option<(u32, Vec<Vec<Complex64>>)>,
+    #[serde(skip)]
+    changes: VecDeque<f64>,
+    #[serde(skip)]
+    reading: Option<Reading>,
+    #[serde(skip)]
+    fft: Option<(usize, (lle::BufferedFft<f64>, lle::BufferedFft<f64>))>,
+}
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Self {
+            tolerance: 1E-9,
+            last: None,
+            changes: VecDeque::new(),
+            reading: None,
+            fft: None,
+        }
+    }
+}
+
+/// Correlation `Σ cur_μ conj(last_μ) e^{iμs}` of the spectra, with its first two derivatives in `s`
+fn correlation(product: &[Complex64], s: f64) -> [Complex64; 3] {
+    let n = product.len();
+    product
+        .iter()
+        .enumerate()
+        .fold([Complex64::default(); 3], |[c, d, dd], (k, p)| {
+            let mu = lle::freq_at(n, k) as f64;
+            let x = p * Complex64::from_polar(1., mu * s);
+            let i_mu = Complex64::new(0., mu);
+            [c + x, d + i_mu * x, dd - mu * mu * x]
+        })
+}
+
+/// Shift in θ of the field of the spectrum `last` maximizing `|C(s)|`, the modulus of its
+/// cross-correlation with the one of `cur`, from the best grid point refined in between,
+/// with `C(s)`. `product` is `cur · last*`, `corr` its inverse transform
+pub(super) fn align(product: &[Complex64], corr: &[Complex64]) -> Option<(f64, Complex64)> {
+    let n = product.len();
+    let j = (0..n).max_by(|&x, &y| corr[x].norm_sqr().total_cmp(&corr[y].norm_sqr()))?;
+    // the sign of the shift of the grid depends on the convention of the transform
+    let s = TAU * j as f64 / n as f64;
+    let s = if s > TAU / 2. { s - TAU } else { s };
+    let mut s = [s, -s].into_iter().max_by(|&x, &y| {
+        let c = |s| correlation(product, s)[0].norm_sqr();
+        c(x).total_cmp(&c(y))
+    })?;
+    // maximize |C(s)|² between the grid points
+    for _ in 0..NEWTON {
+        let [c, d, dd] = correlation(product, s);
+        let slope = 2. * (c.conj() * d).re;
+        let curvature = 2. * (d.norm_sqr() + (c.conj() * dd).re);
+        if curvature >= 0. {
+            break;
+        }
+        let next = s - slope / curvature;
+        if correlation(product, next)[0].norm() < c.norm() {
+            break;
+        }
+        s = next;
+    }
+    Some((s, correlation(product, s)[0]))
+}
+
+/// Squared distance between the spectra `cur` and `last`, as is and minimized over a shift
+/// in θ and a global phase of `last`, with the shift.
+/// `corr` is the inverse transform of the product of the spectra
+fn distances(cur: &[Complex64], last: &[Complex64], corr: &[Complex64]) -> (f64, f64, f64) {
+    let norm = |x: &[Complex64]| x.iter().map(|x| x.norm_sqr()).sum::<f64>();
+    let raw = cur.iter().zip(last).map(|(a, b)| (a - b).norm_sqr()).sum();
+    let product = cur
+        .iter()
+        .zip(last)
+        .map(|(a, b)| a * b.conj())
+        .collect::<Vec<_>>();
+    let Some((s, c)) = align(&product, corr) else {
+        return (raw, raw, 0.);
+    };
+    let aligned = (norm(cur) + norm(last) - 2. * c.norm()).max(0.);
+    (raw, aligned.min(raw), s)
+}
+
+/// Regime from the changes per step, the oldest first
+fn regime(change: f64, raw: f64, changes: &VecDeque<f64>, tolerance: f64) -> Option<Regime> {
+    if raw <= tolerance {
+        return Some(Regime::Stationary);
+    }
+    if change <= tolerance {
+        return Some(Regime::Drifting);
+    }
+    if changes.len() < WINDOW {
+        return None;
+    }
+    let (early, late) = changes.iter().enumerate().fold((0., 0.), |(e, l), (i, x)| {
+        if i < WINDOW / 2 {
+            (e + x, l)
+        } else {
+            (e, l + x)
+        }
+    });
+    Some(if late >= 0.5 * early {
+        Regime::Oscillating
+    } else {
+        Regime::Converging
+    })
+}
+
+impl Convergence {
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            tolerance: self.tolerance,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn regime(&self) -> Option<Regime> {
+        self.reading.and_then(|r| r.regime)
+    }
+
+    /// Compare the field after a batch of `steps` ending at `step` with the one before
+    pub(crate) fn update<P, S: Simulator>(&mut self, core: &Core<P, S>, step: u32, steps: u32) {
+        let mut state = core.simulator.get_owned_state();
+        let spectra = state
+            .components_mut()
+            .into_iter()
+            .map(|f| {
+                let n = f.len();
+                let fft = match &mut self.fft {
+                    Some((len, fft)) if *len == n => fft,
+                    fft => &mut fft.insert((n, lle::BufferedFft::new(n))).1,
+                };
+                let mut f = f.to_vec();
+                fft.0.fft_process(&mut f);
+                f
+            })
+            .collect::<Vec<_>>();
+        // the state may have been replaced since the last batch
+        let last = self
+            .last
+            .take()
+            .filter(|(s, _)| steps > 0 && s.checked_add(steps) == Some(step));
+        let Some((_, last)) = last.filter(|(_, l)| l.len() == spectra.len()) else {
+            self.last = Some((step, spectra));
+            self.changes.clear();
+            self.reading = None;
+            return;
+        };
+        let (mut raw, mut aligned, mut norm, mut shift) = (0., 0., 0., None);
+        for (cur, prev) in spectra.iter().zip(&last) {
+            if cur.len() != prev.len() {
+                continue;
+            }
+            let mut corr = cur
+                .iter()
+                .zip(prev)
+                .map(|(a, b)| a * b.conj())
+                .collect::<Vec<_>>();
+            if let Some((_, fft)) = &mut self.fft {
+                fft.1.fft_process(&mut corr);
+            }
+            let (r, a, s) = distances(cur, prev, &corr);
+            raw += r;
+            aligned += a;
+            norm += cur.iter().map(|x| x.norm_sqr()).sum::<f64>();
+            shift.get_or_insert(s);
+        }
+        self.last = Some((step, spectra));
+        let per_step = |d: f64| {
+            if norm > 0. {
+                (d / norm).sqrt() / steps as f64
+            } else {
+                0.
+            }
+        };
+        let (change, raw) = (per_step(aligned), per_step(raw));
+        if self.changes.len() == WINDOW {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(change);
+        self.reading = Some(Reading {
+            change,
+            raw,
+            drift: shift.unwrap_or_default() / steps as f64,
+            regime: regime(change, raw, &self.changes, self.tolerance),
+        });
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Convergence").on_hover_text(
+                "Change of the field per step, relative to its norm,\n\
+                after removing the drift in θ and the global phase rotation",
+            );
+            if let Some(r) = self.reading {
+                let label = ui.label(format!("{:.2E}", r.change));
+                label.on_hover_text(format!(
+                    "Without removing the drift: {:.2E}\nDrift: {:.3E} rad/step",
+                    r.raw, r.drift
+                ));
+                if let Some(regime) = r.regime {
+                    ui.strong(regime.desc());
+                }
+            } else {
+                ui.weak("run to measure");
+            }
+            ui.add(
+                egui::DragValue::new(&mut self.tolerance)
+                    .speed(1E-10)
+                    .range(0. ..=f64::INFINITY)
+                    .prefix("tol ")
+                    .custom_formatter(|x, _| format!("{x:.1E}")),
+            )
+            .on_hover_text("Change per step under which the field is stationary");
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spectrum(fft: &mut lle::BufferedFft<f64>, f: impl Fn(f64) -> Complex64) -> Vec<Complex64> {
+        let n = 128;
+        let mut v = (0..n)
+            .map(|j| f(TAU * j as f64 / n as f64 - TAU / 2.))
+            .collect::<Vec<_>>();
+        fft.fft_process(&mut v);
+        v
+    }
+
+    #[test]
+    fn drift_and_phase_removed() {
+        let (mut forward, mut inverse) = lle::BufferedFft::new(128);
+        let soliton = |shift: f64, phase: f64| {
+            move |t: f64| Complex64::from_polar(1. / (4. * (t - shift)).cosh(), phase)
+        };
+        let last = spectrum(&mut forward, soliton(0., 0.));
+        // a fraction of the grid spacing
+        let cur = spectrum(&mut forward, soliton(0.013, 0.4));
+        let mut corr = cur
+            .iter()
+            .zip(&last)
+            .map(|(a, b)| a * b.conj())
+            .collect::<Vec<_>>();
+        inverse.fft_process(&mut corr);
+        let (raw, aligned, shift) = distances(&cur, &last, &corr);
+        assert!(aligned < 1E-8 * raw);
+        assert!((shift.abs() - 0.013).abs() < 1E-6);
+    }
+
+    #[test]
+    fn regimes() {
+        let tol = 1E-9;
+        let mut changes = VecDeque::new();
+        assert_eq!(
+            regime(1E-10, 1E-10, &changes, tol),
+            Some(Regime::Stationary)
+        );
+        assert_eq!(regime(1E-10, 1E-3, &changes, tol), Some(Regime::Drifting));
+        assert_eq!(regime(1E-3, 1E-3, &changes, tol), None);
+        changes.extend((0..WINDOW).map(|i| 1E-3 * (1. + (i as f64).sin() / 2.)));
+        assert_eq!(regime(1E-3, 1E-3, &changes, tol), Some(Regime::Oscillating));
+        changes = (0..WINDOW).map(|i| 1E-3 * 0.7f64.powi(i as i32)).collect();
+        assert_eq!(regime(1E-3, 1E-3, &changes, tol), Some(Regime::Converging));
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     random::RandomNoise,
 };
 
-use super::storage::CoreStorage;
+use super::{automation::Automation, links::Links, storage::CoreStorage};
 
 #[derive(Debug)]
 pub struct Core<C, S> {
@@ -13,6 +13,8 @@ pub struct Core<C, S> {
     pub(crate) controller: C,
     pub(crate) simulator: S,
     pub(crate) random: RandomNoise,
+    pub(crate) automation: Automation,
+    pub(crate) links: Links,
 }
 
 impl<C, S> Default for Core<C, S>
@@ -28,6 +30,8 @@ where
             controller,
             simulator,
             random: RandomNoise::default(),
+            automation: Automation::default(),
+            links: Links::default(),
         }
     }
 }
@@ -45,9 +49,19 @@ where
             dim,
             simulator,
             random,
+            automation: Automation::default(),
+            links: Links::default(),
         }
     }
     pub fn sync_paras(&mut self) {
+        self.sync_paras_at(self.simulator.cur_step());
+    }
+    /// Sync the parameters with the schedules evaluated at `step` and the linked parameters,
+    /// for when the steps are run by another copy of the core
+    pub(crate) fn sync_paras_at(&mut self, step: u32) {
+        self.controller.clear_driven();
+        self.automation.apply(&mut self.controller, step);
+        self.links.apply(&mut self.controller);
         self.controller.sync_paras(&mut self.simulator);
     }
     pub fn add_random(&mut self) {
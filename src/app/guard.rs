@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::controller::{Components, Controller, Simulator, StoreState};
+
+use super::{Core, CoreStorage};
+
+/// Good snapshots kept to roll back to
+const DEPTH: usize = 4;
+/// Growth of the mean power in a single batch taken as a blow-up
+const GROWTH: f64 = 1E3;
+
+/// Rolls the core back to the last good snapshot when the field diverges.
+///
+/// Each rollback consumes its snapshot, so diverging again right after resuming
+/// goes further back.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct Guard<P, S: Simulator> {
+    enabled: bool,
+    /// with the step they were taken at
+    #[serde(skip)]
+    snapshots: VecDeque<(u32, CoreStorage<P, S>)>,
+    /// mean power of the last good state
+    #[serde(skip)]
+    power: Option<f64>,
+}
+
+impl<P, S: Simulator> Default for Guard<P, S> {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            snapshots: VecDeque::new(),
+            power: None,
+        }
+    }
+}
+
+/// Mean `|ψ|²` of the fields, `None` if any value isn't finite
+fn mean_power(fields: &[&mut [lle::num_complex::Complex64]]) -> Option<f64> {
+    let mut sum = 0.;
+    let mut len = 0;
+    for x in fields.iter().flat_map(|f| f.iter()) {
+        if !x.is_finite() {
+            return None;
+        }
+        sum += x.norm_sqr();
+        len += 1;
+    }
+    Some(if len == 0 { 0. } else { sum / len as f64 })
+}
+
+impl<P, S> Guard<P, S>
+where
+    P: Controller<S> + Clone,
+    S: Simulator,
+{
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Forget the snapshots, when the core is replaced
+    pub(crate) fn clear(&mut self) {
+        self.snapshots.clear();
+        self.power = None;
+    }
+
+    /// Check the state after a batch ending at `step`, snapshotting it if it's good.
+    /// Returns the step of the core if it diverged, the one rolled back to if a snapshot is left
+    pub(crate) fn check(&mut self, core: &mut Core<P, S>, step: u32) -> Option<u32> {
+        if !self.enabled {
+            return None;
+        }
+        let mut state = core.simulator.get_owned_state();
+        let power = mean_power(&state.components_mut());
+        let diverged = match (power, self.power) {
+            (None, _) => true,
+            (Some(p), Some(last)) => p > GROWTH * last.max(1.),
+            (Some(_), None) => false,
+        };
+        if !diverged {
+            self.power = power;
+            if self.snapshots.len() == DEPTH {
+                self.snapshots.pop_front();
+            }
+            self.snapshots.push_back((step, (&*core).into()));
+            return None;
+        }
+        let mut toasts = crate::notify::TOASTS.lock();
+        let Some((good, snapshot)) = self.snapshots.pop_back() else {
+            toasts.error(format!(
+                "The field diverged at step {step}, with no snapshot to roll back to.\n\
+                Try a smaller Δt"
+            ));
+            return Some(step);
+        };
+        *core = snapshot.into();
+        let mut state = core.simulator.get_owned_state();
+        self.power = mean_power(&state.components_mut());
+        toasts.warning(format!(
+            "The field diverged at step {step}, rolled back to step {good}.\n\
+            Try a smaller Δt"
+        ));
+        Some(good)
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Divergence guard")
+            .on_hover_text(
+                "Pause and roll back to the last good state\n\
+                when the field turns NaN or blows up",
+            );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lle::num_complex::Complex64;
+
+    use super::*;
+
+    #[test]
+    fn non_finite() {
+        let mut a = vec![Complex64::new(1., 1.); 4];
+        let mut b = vec![Complex64::new(0., 2.); 4];
+        assert_eq!(mean_power(&[&mut a[..], &mut b[..]]), Some(3.));
+        b[2].re = f64::NAN;
+        assert_eq!(mean_power(&[&mut a[..], &mut b[..]]), None);
+        b[2] = Complex64::new(f64::INFINITY, 0.);
+        assert_eq!(mean_power(&[&mut a[..], &mut b[..]]), None);
+    }
+}
@@ -0,0 +1,167 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use crate::controller::{Controller, Simulator, StoreState};
+
+use super::Core;
+
+const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+/// Oldest edits are dropped beyond
+const MAX_ENTRIES: usize = 200;
+
+/// Undo/redo stacks of the controller edits made in the control panel.
+///
+/// The controller is compared in its serde form before and after the panel,
+/// so the values written by the automation and the links are not recorded.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct History<C, S: StoreState> {
+    /// also go back to the state of the simulator when the edit was made
+    pub(crate) restore_state: bool,
+    #[serde(skip)]
+    undo: Vec<Entry<C, S>>,
+    #[serde(skip)]
+    redo: Vec<Entry<C, S>>,
+    /// controller at the beginning of the frame, with its serde form
+    #[serde(skip)]
+    before: Option<(String, C)>,
+    /// the last entry was pushed by the drag still going on
+    #[serde(skip)]
+    dragging: bool,
+}
+
+struct Entry<C, S: StoreState> {
+    controller: C,
+    state: Option<S::OwnedState>,
+}
+
+impl<C, S: StoreState> Default for History<C, S> {
+    fn default() -> Self {
+        Self {
+            restore_state: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            before: None,
+            dragging: false,
+        }
+    }
+}
+
+impl<C, S> History<C, S>
+where
+    C: Controller<S> + Clone + serde::Serialize,
+    S: Simulator,
+{
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            restore_state: self.restore_state,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.before = None;
+        self.dragging = false;
+    }
+
+    /// Snapshot the controller before the edits of this frame
+    pub(crate) fn begin(&mut self, core: &Core<C, S>) {
+        self.before = ron::to_string(&core.controller)
+            .ok()
+            .map(|s| (s, core.controller.clone()));
+    }
+
+    /// Record the edits since [`Self::begin`], coalescing those made while the pointer is held
+    pub(crate) fn end(&mut self, ctx: &egui::Context, core: &Core<C, S>) {
+        let held = ctx.input(|i| i.pointer.any_down());
+        let Some((before, controller)) = self.before.take() else {
+            return;
+        };
+        if ron::to_string(&core.controller).is_ok_and(|after| after != before) {
+            if !(self.dragging && held) {
+                let state = self.restore_state.then(|| core.simulator.get_owned_state());
+                self.undo.push(Entry { controller, state });
+                if self.undo.len() > MAX_ENTRIES {
+                    self.undo.remove(0);
+                }
+                self.redo.clear();
+            }
+            self.dragging = held;
+        } else if !held {
+            self.dragging = false;
+        }
+    }
+
+    /// Undo or redo on the shortcuts, if no text field takes the keys.
+    /// Returns whether the simulator state was restored
+    pub(crate) fn handle_shortcuts(&mut self, ctx: &egui::Context, core: &mut Core<C, S>) -> bool {
+        if ctx.wants_keyboard_input() {
+            return false;
+        }
+        // checked first, as the undo shortcut ignores the extra shift
+        if ctx.input_mut(|i| i.consume_shortcut(&REDO)) {
+            self.redo(core)
+        } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO)) {
+            self.undo(core)
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn undo(&mut self, core: &mut Core<C, S>) -> bool {
+        // not an edit of this frame
+        self.before = None;
+        Self::swap(&mut self.undo, &mut self.redo, core)
+    }
+
+    pub(crate) fn redo(&mut self, core: &mut Core<C, S>) -> bool {
+        self.before = None;
+        Self::swap(&mut self.redo, &mut self.undo, core)
+    }
+
+    /// Go to the last entry of `from`, saving the current one to `to`
+    fn swap(from: &mut Vec<Entry<C, S>>, to: &mut Vec<Entry<C, S>>, core: &mut Core<C, S>) -> bool {
+        let Some(entry) = from.pop() else {
+            return false;
+        };
+        to.push(Entry {
+            controller: std::mem::replace(&mut core.controller, entry.controller),
+            state: entry
+                .state
+                .as_ref()
+                .map(|_| core.simulator.get_owned_state()),
+        });
+        if let Some(state) = entry.state {
+            core.simulator.set_owned_state(state);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, core: &mut Core<C, S>) -> bool {
+        let mut restored = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!self.undo.is_empty(), egui::Button::new("⮪"))
+                .on_hover_text(format!("Undo ({})", ui.ctx().format_shortcut(&UNDO)))
+                .clicked()
+            {
+                restored |= self.undo(core);
+            }
+            if ui
+                .add_enabled(!self.redo.is_empty(), egui::Button::new("⮫"))
+                .on_hover_text(format!("Redo ({})", ui.ctx().format_shortcut(&REDO)))
+                .clicked()
+            {
+                restored |= self.redo(core);
+            }
+            ui.checkbox(&mut self.restore_state, "with state")
+                .on_hover_text("Undo also restores the field of the time of the edit");
+        });
+        restored
+    }
+}
@@ -1,4 +1,4 @@
-use ui_traits::ControllerUI;
+use ui_traits::{ControllerUI, DisplayStr};
 
 use super::*;
 
@@ -8,6 +8,7 @@ pub(crate) struct PlayControl {
     pub(crate) destruct: bool,
     pub(crate) step: bool,
     pub(crate) refresh: bool,
+    pub(crate) reseed: bool,
 }
 
 impl<P, S, V, T, D> GenApp<P, S, V, T, D>
@@ -33,18 +34,36 @@ where
             is_init,
             core,
             running,
+            runner,
+            model,
+            switch_to,
+            seed,
+            recorder,
             ..
         } = self;
         if !*is_init {
             let Core {
-                dim,
-                controller,
-                simulator,
-                random,
+                dim, controller, ..
             } = core;
             *running = false;
             *is_init = egui::Window::new("Welcome to LLE Simulator")
                 .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Model");
+                        let mut selected = *model;
+                        egui::ComboBox::from_id_salt("model picker")
+                            .selected_text(selected.desc())
+                            .show_ui(ui, |ui| selected.show_controller(ui));
+                        if selected != *model {
+                            *switch_to = Some(selected);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Initial state");
+                        seed.show_controller(ui);
+                    });
+                    ui.separator();
+
                     controller.show_in_start_window(dim, ui);
 
                     ui.centered_and_justified(|ui| {
@@ -57,8 +76,12 @@ where
                 .inner
                 .unwrap_or(false);
             if *is_init {
-                *simulator = controller.construct_engine(*dim);
-                simulator.add_rand(random);
+                core.simulator = core.controller.construct_engine(core.dim);
+                if seed.apply(core).notify_global().is_none() {
+                    core.add_random();
+                }
+                runner.restart();
+                recorder.restore(0, core);
             }
         }
     }
@@ -68,6 +91,18 @@ where
         let Self {
             core,
             running,
+            runner,
+            budget,
+            history,
+            recorder,
+            guard,
+            convergence,
+            compare,
+            phase_diagram,
+            comb,
+            triggers,
+            init_editor,
+            seed,
             views,
             show_dispersion,
             file_state,
@@ -85,8 +120,16 @@ where
             mut destruct,
             mut step,
             mut refresh,
+            mut reseed,
         } = PlayControl::default();
 
+        let cur_step = runner.cur_step(core);
+        if history.handle_shortcuts(ctx, core) {
+            runner.invalidate();
+            recorder.set_state(cur_step, core);
+        }
+        history.begin(core);
+
         egui::SidePanel::left("control_panel").show(ctx, |ui| {
             // cause display error of super and subscript in easy_mark
             //ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
@@ -105,6 +148,11 @@ where
 
                 core.controller.show_in_control_panel(ui);
 
+                if history.show(ui, core) {
+                    runner.invalidate();
+                    recorder.set_state(cur_step, core);
+                }
+
                 ui.separator();
 
                 let button_text = if *running { "⏸" } else { "⏵" };
@@ -130,6 +178,13 @@ where
                         .ui(ui)
                         .on_hover_text("Refresh the state to 0")
                         .clicked();
+                    let reseed_button = attractive_button("🌱", None).ui(ui).on_hover_text(
+                        format!("Reseed: {}\nRight click to choose the preset", seed.desc()),
+                    );
+                    reseed = reseed_button.clicked();
+                    reseed_button.context_menu(|ui| seed.show_controller(ui));
+                    ui.toggle_value(&mut init_editor.open, "✏")
+                        .on_hover_text("Edit the initial state");
                     reset = attractive_button("⏹", None)
                         .ui(ui)
                         .on_hover_text("Reset model")
@@ -149,9 +204,46 @@ where
                 )
                 .ui(ui);
 
+                runner.show(ui);
+
+                budget.show_controller(ui);
+
+                runner.adaptive.show_controller(ui);
+
+                convergence.show(ui);
+
+                let step = runner.cur_step(core);
+                core.automation.show(ui, &mut core.controller, step);
+                core.links.show(ui, &mut core.controller);
+                guard.show(ui);
+                triggers.show(ui);
+
                 core.random.show(ui, add_rand);
 
-                scout.show(core, ui);
+                if let Some(adopted) = scout.show(core, ui) {
+                    if scout.keep_main {
+                        check_points.push(checkpoint::CheckPoint {
+                            name: Some(format!(
+                                "Main at step {cur_step}, before adopting a preview"
+                            )),
+                            state: (&*core).into(),
+                        });
+                    }
+                    *core = adopted;
+                    history.clear();
+                    guard.clear();
+                    // the adopted engine starts over, the main step carries on
+                    runner.continue_at(cur_step, core);
+                    recorder.restore(cur_step, core);
+                    views.adjust_to_state(core.simulator.states());
+                }
+
+                compare.show(ui, core);
+
+                ui.toggle_value(&mut phase_diagram.open, "🗺 Phase diagram")
+                    .on_hover_text("Classify the regimes over a grid of two parameters");
+                ui.toggle_value(&mut comb.open, "📊 Comb metrics")
+                    .on_hover_text("Power, bandwidth, recoil and efficiency of the comb");
 
                 // advanced simulation control
                 ui.separator();
@@ -170,6 +262,10 @@ where
                 attractive_head("Save/Load model", ui.visuals().strong_text_color()).ui(ui);
 
                 if let Some(true) = file_state.show_save_load(ui, core).notify_global() {
+                    history.clear();
+                    guard.clear();
+                    runner.restart();
+                    recorder.restore(runner.cur_step(core), core);
                     views.adjust_to_state(core.simulator.states());
                 }
 
@@ -178,6 +274,10 @@ where
                 attractive_head("Checkpoints", ui.visuals().strong_text_color()).ui(ui);
 
                 if check_points.show(ui, core) {
+                    history.clear();
+                    guard.clear();
+                    runner.restart();
+                    recorder.restore(runner.cur_step(core), core);
                     views.adjust_to_state(core.simulator.states());
                 }
                 file_checkpoints
@@ -186,6 +286,16 @@ where
 
                 ui.separator();
 
+                attractive_head("Session journal", ui.visuals().strong_text_color()).ui(ui);
+
+                if recorder.show(ui, core, runner) {
+                    history.clear();
+                    guard.clear();
+                    views.adjust_to_state(core.simulator.states());
+                }
+
+                ui.separator();
+
                 ui.horizontal(|ui| {
                     ui.label("Slider length");
                     ui.add(DragValue::new(slider_len));
@@ -204,11 +314,20 @@ where
                 crate::util::show_profiler(profiler, ui);
             });
         });
+
+        if init_editor.show(ctx, core) {
+            runner.invalidate();
+            guard.clear();
+            recorder.set_state(cur_step, core);
+            views.adjust_to_state(core.simulator.states());
+        }
+        history.end(ctx, core);
         PlayControl {
             reset,
             destruct,
             step,
             refresh,
+            reseed,
         }
     }
 
@@ -220,22 +339,42 @@ where
             destruct,
             step,
             refresh,
+            reseed,
         } = play_control;
 
         let Self {
             core,
             is_init,
             running,
+            runner,
+            budget,
+            history,
+            recorder,
+            guard,
+            convergence,
+            compare,
+            triggers,
             views,
             scout,
             add_rand,
             file_state,
             file_checkpoints,
+            check_points,
+            seed,
             ..
         } = self;
 
+        let cur_step = runner.cur_step(core);
+        if reset || destruct || refresh || reseed {
+            runner.restart();
+            guard.clear();
+        }
+        if reset || destruct {
+            history.clear();
+        }
         if reset {
             core.reset();
+            recorder.restore(runner.cur_step(core), core);
             *running = false;
             *views = Default::default();
             *file_state = FileManager::default_state();
@@ -251,31 +390,108 @@ where
             core.simulator = core
                 .controller
                 .construct_engine_random_init(core.dim, &mut core.random);
+            recorder.refresh(cur_step);
             return true;
         }
-        if *running || step {
-            core.sync_paras();
-            scout.sync_paras(core);
-            scout.tick(core);
-            if *add_rand {
-                puffin_egui::puffin::profile_scope!("add random");
+        if reseed {
+            core.simulator = core.controller.construct_engine(core.dim);
+            if seed.apply(core).notify_global().is_none() {
                 core.add_random();
             }
+            recorder.restore(runner.cur_step(core), core);
+            return true;
+        }
+        let mut batch = runner.poll(core);
+        if let Some(b) = &batch {
+            recorder.batch_done(b);
+        }
+        // before dispatching the next batch, so a pause stops right after the checked one
+        if let Some(b) = &batch
+            && check_batch(
+                core,
+                runner,
+                guard,
+                convergence,
+                recorder,
+                triggers,
+                check_points,
+                b,
+            )
+        {
+            *running = false;
+        }
+        let replaying = recorder.is_replaying();
+        if !replaying {
+            // every frame, so the linked and scheduled values show while paused,
+            // and the previews and the phase diagram see them
+            core.sync_paras_at(runner.cur_step(core));
+            scout.sync_paras(core);
+        }
+        if replaying {
+            // the journal sets the parameters and the noise, bypassing the automation
+            if (*running || step)
+                && runner.is_ready()
+                && let Some((steps, noise, step_dist)) = recorder.next_batch(core, runner)
+            {
+                core.controller.sync_paras(&mut core.simulator);
+                let stepped = runner.step(core, steps, noise, step_dist);
+                if let Some(b) = &stepped
+                    && check_batch(
+                        core,
+                        runner,
+                        guard,
+                        convergence,
+                        recorder,
+                        triggers,
+                        check_points,
+                        b,
+                    )
+                {
+                    *running = false;
+                }
+                batch = batch.or(stepped);
+            }
+        } else if *running || step {
+            let cur_step = runner.cur_step(core);
+            scout.tick(core);
 
-            let Core {
-                controller,
-                simulator,
-                ..
-            } = core;
-
+            puffin_egui::puffin::profile_scope!("calculate");
+            let mut steps = budget.steps(core.controller.steps());
+            if let Some(left) = triggers.steps_until(cur_step) {
+                steps = steps.min(left);
+            }
+            if runner.is_ready() {
+                recorder.batch(cur_step, core, steps, *add_rand);
+                compare.step(steps, *add_rand, runner.background);
+            }
+            // run in the foreground, not seen by the poll above
+            let stepped = runner.step(core, steps, *add_rand, None);
+            if let Some(b) = &stepped {
+                recorder.batch_done(b);
+            }
+            if let Some(b) = &stepped
+                && check_batch(
+                    core,
+                    runner,
+                    guard,
+                    convergence,
+                    recorder,
+                    triggers,
+                    check_points,
+                    b,
+                )
             {
-                puffin_egui::puffin::profile_scope!("calculate");
-                simulator.run(controller.steps());
-                scout.poll_previews(controller.steps(), *add_rand);
+                *running = false;
             }
-            return true;
+            batch = batch.or(stepped);
+            scout.poll_previews(steps, *add_rand);
+        } else {
+            budget.pause();
+        }
+        if let Some(batch) = batch {
+            budget.record(batch.steps, batch.elapsed);
         }
-        false
+        batch.is_some()
     }
 
     pub(crate) fn update_views(
@@ -290,6 +506,9 @@ where
             views,
             show_dispersion,
             scout,
+            compare,
+            phase_diagram,
+            comb,
             #[cfg(feature = "gpu")]
             render_state,
             debugger,
@@ -297,6 +516,15 @@ where
         } = self;
 
         scout.push_to_views(views, ShowOn::Both, running);
+        scout.show_grid(ctx);
+        compare.push_to_views(core, views, running);
+        phase_diagram.show(
+            ctx,
+            core,
+            #[cfg(feature = "gpu")]
+            render_state,
+        );
+        comb.show(ctx, core);
         dispersion::add_dispersion_curve(show_dispersion, core, views);
         debugger::add_debugger(core, views, debugger);
 
@@ -309,3 +537,35 @@ where
         );
     }
 }
+
+/// Check a finished batch with the divergence guard, measure its convergence,
+/// then check the stop conditions. Returns whether to pause.
+/// A replay only measures, the journal already holds the restores of the guard
+#[allow(clippy::too_many_arguments)]
+pub(super) fn check_batch<P, S>(
+    core: &mut Core<P, S>,
+    runner: &mut Runner<P, S>,
+    guard: &mut Guard<P, S>,
+    convergence: &mut Convergence,
+    recorder: &mut Recorder<P, S>,
+    triggers: &mut Triggers,
+    check_points: &mut checkpoint::CheckPoints<CoreStorage<P, S>>,
+    batch: &runner::Batch,
+) -> bool
+where
+    P: Clone + Controller<S> + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+{
+    let step = runner.cur_step(core);
+    let replaying = recorder.is_replaying();
+    if !replaying && let Some(good) = guard.check(core, step) {
+        runner.continue_at(good, core);
+        recorder.restore(good, core);
+        return true;
+    }
+    convergence.update(core, step, batch.steps);
+    !replaying
+        && triggers
+            .check(core, check_points, convergence.regime(), step, batch.steps)
+            .pause
+}
@@ -0,0 +1,487 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{PI, TAU},
+};
+
+use anyhow::Context;
+use lle::num_complex::Complex64;
+use ui_traits::{ControllerUI, DisplayStr, Params};
+
+use crate::{
+    controller::{Components, Controller, Simulator, StoreState},
+    expr::Expr,
+    notify::ResultExt,
+};
+
+use super::Core;
+
+/// Control points of a drawn envelope, spread over θ ∈ [-π, π)
+const DRAW_POINTS: usize = 64;
+
+/// Window building the state of the simulator, component by component
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct InitEditor {
+    #[serde(skip)]
+    pub(crate) open: bool,
+    profiles: Vec<Profile>,
+    selected: usize,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    enum_iterator::Sequence,
+)]
+enum ProfileKind {
+    #[default]
+    Expression,
+    Drawing,
+    Primitives,
+}
+
+impl DisplayStr for ProfileKind {
+    fn desc(&self) -> &str {
+        match self {
+            ProfileKind::Expression => "Expression",
+            ProfileKind::Drawing => "Drawing",
+            ProfileKind::Primitives => "Primitives",
+        }
+    }
+}
+
+/// ψ(θ) = A(θ) exp(i φ(θ)) of a component
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Profile {
+    kind: ProfileKind,
+    /// A(θ), in terms of `θ` and the parameters of the controller
+    amplitude: String,
+    /// φ(θ)
+    phase: String,
+    drawn: Drawing,
+    primitives: Vec<Primitive>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            kind: ProfileKind::Expression,
+            amplitude: "sech(θ*10)".to_string(),
+            phase: "0".to_string(),
+            drawn: Drawing::default(),
+            primitives: vec![Primitive::default()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Drawing {
+    amplitude: Vec<f64>,
+    phase: Vec<f64>,
+    edit_phase: bool,
+    /// last drawn point, to fill the points skipped by a fast stroke
+    #[serde(skip)]
+    last: Option<(usize, f64)>,
+}
+
+impl Default for Drawing {
+    fn default() -> Self {
+        Self {
+            amplitude: vec![0.; DRAW_POINTS],
+            phase: vec![0.; DRAW_POINTS],
+            edit_phase: false,
+            last: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+enum Primitive {
+    Constant {
+        amplitude: f64,
+        phase: f64,
+    },
+    Sech {
+        amplitude: f64,
+        center: f64,
+        width: f64,
+        phase: f64,
+    },
+    Gaussian {
+        amplitude: f64,
+        center: f64,
+        width: f64,
+        phase: f64,
+    },
+    Cosine {
+        amplitude: f64,
+        mode: i32,
+        phase: f64,
+    },
+}
+
+impl Default for Primitive {
+    fn default() -> Self {
+        Primitive::Sech {
+            amplitude: 1.,
+            center: 0.,
+            width: 0.1,
+            phase: 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_iterator::Sequence)]
+enum PrimitiveKind {
+    Constant,
+    Sech,
+    Gaussian,
+    Cosine,
+}
+
+impl DisplayStr for PrimitiveKind {
+    fn desc(&self) -> &str {
+        match self {
+            PrimitiveKind::Constant => "Constant",
+            PrimitiveKind::Sech => "Sech",
+            PrimitiveKind::Gaussian => "Gaussian",
+            PrimitiveKind::Cosine => "Cosine",
+        }
+    }
+}
+
+/// θ of the `j`th of `n` points
+pub(super) fn theta(j: usize, n: usize) -> f64 {
+    -PI + TAU * j as f64 / n as f64
+}
+
+impl Primitive {
+    fn kind(&self) -> PrimitiveKind {
+        match self {
+            Primitive::Constant { .. } => PrimitiveKind::Constant,
+            Primitive::Sech { .. } => PrimitiveKind::Sech,
+            Primitive::Gaussian { .. } => PrimitiveKind::Gaussian,
+            Primitive::Cosine { .. } => PrimitiveKind::Cosine,
+        }
+    }
+
+    fn new(kind: PrimitiveKind) -> Self {
+        match kind {
+            PrimitiveKind::Constant => Primitive::Constant {
+                amplitude: 1.,
+                phase: 0.,
+            },
+            PrimitiveKind::Sech => Primitive::default(),
+            PrimitiveKind::Gaussian => Primitive::Gaussian {
+                amplitude: 1.,
+                center: 0.,
+                width: 0.1,
+                phase: 0.,
+            },
+            PrimitiveKind::Cosine => Primitive::Cosine {
+                amplitude: 1.,
+                mode: 1,
+                phase: 0.,
+            },
+        }
+    }
+
+    fn at(&self, theta: f64) -> Complex64 {
+        let (amplitude, phase) = match *self {
+            Primitive::Constant { amplitude, phase } => (amplitude, phase),
+            Primitive::Sech {
+                amplitude,
+                center,
+                width,
+                phase,
+            } => (amplitude / ((theta - center) / width).cosh(), phase),
+            Primitive::Gaussian {
+                amplitude,
+                center,
+                width,
+                phase,
+            } => (
+                amplitude * (-((theta - center) / width).powi(2) / 2.).exp(),
+                phase,
+            ),
+            Primitive::Cosine {
+                amplitude,
+                mode,
+                phase,
+            } => (amplitude * (mode as f64 * theta + phase).cos(), 0.),
+        };
+        Complex64::from_polar(amplitude, phase)
+    }
+}
+
+impl ControllerUI for Primitive {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        let mut kind = self.kind();
+        egui::ComboBox::from_id_salt(ui.next_auto_id())
+            .selected_text(kind.desc())
+            .show_ui(ui, |ui| kind.show_controller(ui));
+        if kind != self.kind() {
+            *self = Primitive::new(kind);
+        }
+        let drag = |ui: &mut egui::Ui, v: &mut f64, prefix: &str| {
+            ui.add(egui::DragValue::new(v).speed(1E-2).prefix(prefix));
+        };
+        match self {
+            Primitive::Constant { amplitude, phase } => {
+                drag(ui, amplitude, "A ");
+                drag(ui, phase, "φ ");
+            }
+            Primitive::Sech {
+                amplitude,
+                center,
+                width,
+                phase,
+            }
+            | Primitive::Gaussian {
+                amplitude,
+                center,
+                width,
+                phase,
+            } => {
+                drag(ui, amplitude, "A ");
+                drag(ui, center, "θ₀ ");
+                drag(ui, width, "w ");
+                drag(ui, phase, "φ ");
+            }
+            Primitive::Cosine {
+                amplitude,
+                mode,
+                phase,
+            } => {
+                drag(ui, amplitude, "A ");
+                ui.add(egui::DragValue::new(mode).prefix("m "));
+                drag(ui, phase, "φ ");
+            }
+        }
+    }
+}
+
+impl Drawing {
+    /// Periodic linear interpolation of the control points
+    fn at(&self, theta: f64) -> Complex64 {
+        let x = (theta + PI) / TAU * DRAW_POINTS as f64;
+        let i = (x.floor() as usize).min(DRAW_POINTS - 1);
+        let frac = x - i as f64;
+        let lerp = |v: &[f64]| v[i] * (1. - frac) + v[(i + 1) % DRAW_POINTS] * frac;
+        Complex64::from_polar(lerp(&self.amplitude), lerp(&self.phase))
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Draw");
+            ui.selectable_value(&mut self.edit_phase, false, "Amplitude");
+            ui.selectable_value(&mut self.edit_phase, true, "Phase");
+            if ui.button("Clear").clicked() {
+                *self = Self {
+                    edit_phase: self.edit_phase,
+                    ..Default::default()
+                };
+            }
+        });
+        let (name, values) = if self.edit_phase {
+            ("φ", &mut self.phase)
+        } else {
+            ("A", &mut self.amplitude)
+        };
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(j, &v)| [theta(j, DRAW_POINTS), v])
+            .collect::<Vec<_>>();
+        let r = egui_plot::Plot::new("init state drawing")
+            .height(160.)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .include_x(-PI)
+            .include_x(PI)
+            .include_y(0.)
+            .include_y(1.)
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new(name, points.clone()));
+                plot_ui.points(egui_plot::Points::new("", points).radius(2.));
+                plot_ui.pointer_coordinate()
+            });
+        match r.inner {
+            Some(p) if r.response.is_pointer_button_down_on() => {
+                let x = (p.x + PI) / TAU * DRAW_POINTS as f64;
+                let j = (x.round() as isize).rem_euclid(DRAW_POINTS as isize) as usize;
+                let (from, from_v) = self.last.unwrap_or((j, p.y));
+                let (lo, hi) = (from.min(j), from.max(j));
+                for k in lo..=hi {
+                    let t = if hi == lo {
+                        1.
+                    } else {
+                        (k as f64 - from as f64) / (j as f64 - from as f64)
+                    };
+                    values[k] = from_v + (p.y - from_v) * t;
+                }
+                self.last = Some((j, p.y));
+            }
+            _ => self.last = None,
+        }
+    }
+}
+
+impl Profile {
+    /// Values at the `n` points of θ ∈ [-π, π), `vars` being the parameters of the controller
+    fn build(&self, n: usize, vars: &HashMap<String, f64>) -> anyhow::Result<Vec<Complex64>> {
+        match self.kind {
+            ProfileKind::Expression => {
+                let parse = |s: &str, name: &str| -> anyhow::Result<Expr> {
+                    let s = if s.trim().is_empty() { "0" } else { s };
+                    s.parse().with_context(|| format!("Invalid {name} `{s}`"))
+                };
+                let amplitude = parse(&self.amplitude, "amplitude")?;
+                let phase = parse(&self.phase, "phase")?;
+                (0..n)
+                    .map(|j| {
+                        let theta = theta(j, n);
+                        let lookup = |name: &str| match name {
+                            "θ" | "theta" => Some(theta),
+                            _ => vars.get(name).copied(),
+                        };
+                        Ok(Complex64::from_polar(
+                            amplitude.eval(&lookup)?,
+                            phase.eval(&lookup)?,
+                        ))
+                    })
+                    .collect()
+            }
+            ProfileKind::Drawing => Ok((0..n).map(|j| self.drawn.at(theta(j, n))).collect()),
+            ProfileKind::Primitives => Ok((0..n)
+                .map(|j| {
+                    let theta = theta(j, n);
+                    self.primitives.iter().map(|p| p.at(theta)).sum()
+                })
+                .collect()),
+        }
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| self.kind.show_controller(ui));
+        match self.kind {
+            ProfileKind::Expression => {
+                egui::Grid::new("init state expression")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("A(θ) =").on_hover_text(
+                            "Expression of θ ∈ [-π, π) and of the parameters, like `sqrt(2*alpha)`",
+                        );
+                        ui.text_edit_singleline(&mut self.amplitude);
+                        ui.end_row();
+                        ui.label("φ(θ) =");
+                        ui.text_edit_singleline(&mut self.phase);
+                        ui.end_row();
+                    });
+            }
+            ProfileKind::Drawing => self.drawn.show(ui),
+            ProfileKind::Primitives => {
+                ui.label("Sum of");
+                crate::util::show_vector(ui, &mut self.primitives);
+            }
+        }
+    }
+}
+
+fn show_preview(ui: &mut egui::Ui, values: &[Complex64]) {
+    let n = values.len();
+    let line = |name: &str, f: fn(&Complex64) -> f64| {
+        egui_plot::Line::new(
+            name,
+            values
+                .iter()
+                .enumerate()
+                .map(|(j, v)| [theta(j, n), f(v)])
+                .collect::<Vec<_>>(),
+        )
+    };
+    egui_plot::Plot::new("init state preview")
+        .height(160.)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(line("|ψ|", |v| v.norm()));
+            plot_ui.line(line("arg ψ", |v| v.arg()));
+        });
+}
+
+impl InitEditor {
+    /// Show the window if open, returns `true` if the state of `core` is replaced
+    pub(crate) fn show<C, S>(&mut self, ctx: &egui::Context, core: &mut Core<C, S>) -> bool
+    where
+        C: Controller<S>,
+        S: Simulator,
+    {
+        let mut open = self.open;
+        let mut applied = false;
+        egui::Window::new("Initial state")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let mut state: S::OwnedState = core.simulator.get_owned_state();
+                let lens = state
+                    .components_mut()
+                    .iter()
+                    .map(|c| c.len())
+                    .collect::<Vec<_>>();
+                self.profiles.resize_with(lens.len(), Default::default);
+                self.selected = self.selected.min(lens.len() - 1);
+                if lens.len() > 1 {
+                    ui.horizontal(|ui| {
+                        ui.label("Component");
+                        for i in 0..lens.len() {
+                            ui.selectable_value(&mut self.selected, i, i.to_string());
+                        }
+                    });
+                }
+
+                let mut vars = HashMap::new();
+                core.controller.visit_params("", &mut |path, param| {
+                    vars.insert(path.to_string(), param.get_f64());
+                });
+
+                let profile = &mut self.profiles[self.selected];
+                profile.show(ui);
+                ui.separator();
+                match profile.build(lens[self.selected], &vars) {
+                    Ok(values) => show_preview(ui, &values),
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}"));
+                    }
+                }
+
+                if ui
+                    .button("Apply")
+                    .on_hover_text("Replace the state of the simulator")
+                    .clicked()
+                {
+                    let built = self
+                        .profiles
+                        .iter()
+                        .zip(&lens)
+                        .map(|(p, &n)| p.build(n, &vars))
+                        .collect::<anyhow::Result<Vec<_>>>()
+                        .notify_global();
+                    if let Some(built) = built {
+                        for (c, values) in state.components_mut().into_iter().zip(built) {
+                            c.copy_from_slice(&values);
+                        }
+                        core.simulator.set_owned_state(state);
+                        applied = true;
+                    }
+                }
+            });
+        self.open = open;
+        applied
+    }
+}
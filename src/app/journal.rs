@@ -0,0 +1,390 @@
+use anyhow::Context;
+
+use crate::{
+    controller::{Controller, Simulator},
+    file::{Extension, FileManager},
+    notify::ResultExt,
+};
+
+use super::{
+    Core, CoreStorage,
+    runner::{Batch, Runner},
+};
+
+/// Everything that changed a [`Core`] since the start of a recording, in order,
+/// to run it again with the same result
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "S: Simulator, S::OwnedState: serde::Serialize, P: serde::Serialize",
+    deserialize = "S: Simulator, S::OwnedState: for<'a> serde::Deserialize<'a>, P: for<'a> serde::Deserialize<'a>"
+))]
+pub(crate) struct Journal<P, S: Simulator> {
+    start: Option<CoreStorage<P, S>>,
+    /// step of the main core at the start
+    #[serde(default)]
+    start_step: u32,
+    events: Vec<Event<P, S>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "S: Simulator, S::OwnedState: serde::Serialize, P: serde::Serialize",
+    deserialize = "S: Simulator, S::OwnedState: for<'a> serde::Deserialize<'a>, P: for<'a> serde::Deserialize<'a>"
+))]
+enum Event<P, S: Simulator> {
+    /// controller of the next batches, with the scheduled and linked values
+    Params {
+        step: u32,
+        controller: P,
+    },
+    Noise {
+        step: u32,
+        on: bool,
+        std_dev: f64,
+    },
+    /// `steps` steps run by the solver, with the `Δt` set by the step control.
+    /// Journals from before it was recorded replay with the current step control
+    Batch {
+        steps: u32,
+        #[serde(default)]
+        step_dist: Option<f64>,
+    },
+    /// state set to 0 with noise
+    Refresh {
+        step: u32,
+    },
+    /// state from the initial state editor or an undo
+    State {
+        step: u32,
+        state: S::OwnedState,
+    },
+    /// core from a checkpoint, a file or a reseed, at `step`
+    Restore {
+        step: u32,
+        core: CoreStorage<P, S>,
+    },
+}
+
+impl<P, S: Simulator> Default for Journal<P, S> {
+    fn default() -> Self {
+        Self {
+            start: None,
+            start_step: 0,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<P: Controller<S>, S: Simulator> Extension for Journal<P, S> {
+    const EXTENSION: &'static str = "journal";
+    fn extension() -> String {
+        format!("{}.{}", P::EXTENSION, Self::EXTENSION)
+    }
+}
+
+impl<P, S: Simulator> Journal<P, S> {
+    fn steps(&self) -> u64 {
+        self.events
+            .iter()
+            .map(|e| match e {
+                Event::Batch { steps, .. } => *steps as u64,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+enum Mode<P> {
+    Idle,
+    Recording {
+        /// serde form of the last recorded controller
+        params: Option<String>,
+        noise: Option<(bool, f64)>,
+        /// batch sent to the runner, recorded once it's back
+        pending: Option<Pending<P>>,
+    },
+    Replaying {
+        /// index of the next event
+        next: usize,
+        params: Option<P>,
+        noise: (bool, f64),
+    },
+}
+
+/// A batch about to run, with its parameters
+struct Pending<P> {
+    step: u32,
+    controller: P,
+    add_rand: bool,
+    std_dev: f64,
+    steps: u32,
+}
+
+/// Records the [`Journal`] of the main core, or feeds one back to it
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct Recorder<P, S: Simulator> {
+    #[serde(skip)]
+    journal: Journal<P, S>,
+    #[serde(skip, default = "idle")]
+    mode: Mode<P>,
+    #[serde(default = "default_file")]
+    file: FileManager,
+}
+
+fn idle<P>() -> Mode<P> {
+    Mode::Idle
+}
+
+fn default_file() -> FileManager {
+    FileManager::new("Save journal")
+}
+
+impl<P, S: Simulator> Default for Recorder<P, S> {
+    fn default() -> Self {
+        Self {
+            journal: Journal::default(),
+            mode: Mode::Idle,
+            file: default_file(),
+        }
+    }
+}
+
+impl<P, S> Recorder<P, S>
+where
+    P: Controller<S> + Clone + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+{
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            file: self.file.clone_for_save(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_replaying(&self) -> bool {
+        matches!(self.mode, Mode::Replaying { .. })
+    }
+
+    fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Recording { .. })
+    }
+
+    fn push(&mut self, event: Event<P, S>) {
+        if let Mode::Recording { pending, .. } = &mut self.mode {
+            // the core is set, dropping the batch on the way
+            *pending = None;
+            self.journal.events.push(event);
+        }
+    }
+
+    pub(crate) fn refresh(&mut self, step: u32) {
+        self.push(Event::Refresh { step });
+    }
+
+    pub(crate) fn set_state(&mut self, step: u32, core: &Core<P, S>) {
+        if self.is_recording() {
+            let state = core.simulator.get_owned_state();
+            self.push(Event::State { step, state });
+        }
+    }
+
+    pub(crate) fn restore(&mut self, step: u32, core: &Core<P, S>) {
+        if self.is_recording() {
+            let core = core.into();
+            self.push(Event::Restore { step, core });
+        }
+    }
+
+    /// Keep a batch of `steps` about to run on `core` until [`Self::batch_done`],
+    /// replacing the previous one if it was dropped by the runner
+    pub(crate) fn batch(&mut self, step: u32, core: &Core<P, S>, steps: u32, add_rand: bool) {
+        if let Mode::Recording { pending, .. } = &mut self.mode {
+            *pending = Some(Pending {
+                step,
+                controller: core.controller.clone(),
+                add_rand,
+                std_dev: core.random.std_dev(),
+                steps,
+            });
+        }
+    }
+
+    /// Record the kept batch, after the parameters it ran with, once the runner returns it
+    pub(crate) fn batch_done(&mut self, batch: &Batch) {
+        let Mode::Recording {
+            params,
+            noise,
+            pending,
+        } = &mut self.mode
+        else {
+            return;
+        };
+        let Some(Pending {
+            step,
+            controller,
+            add_rand,
+            std_dev,
+            steps,
+        }) = pending.take()
+        else {
+            return;
+        };
+        let events = &mut self.journal.events;
+        if *noise != Some((add_rand, std_dev)) {
+            *noise = Some((add_rand, std_dev));
+            events.push(Event::Noise {
+                step,
+                on: add_rand,
+                std_dev,
+            });
+        }
+        if let Ok(s) = ron::to_string(&controller)
+            && params.as_ref() != Some(&s)
+        {
+            *params = Some(s);
+            events.push(Event::Params { step, controller });
+        }
+        events.push(Event::Batch {
+            steps,
+            step_dist: Some(batch.step_dist),
+        });
+    }
+
+    fn start_replay(
+        &mut self,
+        core: &mut Core<P, S>,
+        runner: &mut Runner<P, S>,
+    ) -> anyhow::Result<()> {
+        let start = self
+            .journal
+            .start
+            .clone()
+            .context("The journal is empty, record or load one first")?;
+        *core = start.into();
+        runner.continue_at(self.journal.start_step, core);
+        self.mode = Mode::Replaying {
+            next: 0,
+            params: None,
+            noise: (false, core.random.std_dev()),
+        };
+        Ok(())
+    }
+
+    /// Apply the journal up to its next batch and return its steps, whether noise is added
+    /// and its `Δt`. The controller and the noise are set from the journal, overriding the edits.
+    pub(crate) fn next_batch(
+        &mut self,
+        core: &mut Core<P, S>,
+        runner: &mut Runner<P, S>,
+    ) -> Option<(u32, bool, Option<f64>)> {
+        let Mode::Replaying {
+            next,
+            params,
+            noise,
+        } = &mut self.mode
+        else {
+            return None;
+        };
+        while let Some(event) = self.journal.events.get(*next) {
+            *next += 1;
+            match event {
+                Event::Params { controller, .. } => *params = Some(controller.clone()),
+                Event::Noise { on, std_dev, .. } => *noise = (*on, *std_dev),
+                Event::Batch { steps, step_dist } => {
+                    if let Some(p) = params {
+                        core.controller = p.clone();
+                    }
+                    core.random.set_std_dev(noise.1);
+                    return Some((*steps, noise.0, *step_dist));
+                }
+                Event::Refresh { .. } => {
+                    core.simulator = core
+                        .controller
+                        .construct_engine_random_init(core.dim, &mut core.random);
+                    runner.restart();
+                }
+                Event::State { state, .. } => {
+                    core.simulator.set_owned_state(state.clone());
+                    runner.invalidate();
+                }
+                Event::Restore { step, core: c } => {
+                    *core = c.clone().into();
+                    runner.continue_at(*step, core);
+                }
+            }
+        }
+        self.mode = Mode::Idle;
+        crate::notify::TOASTS.lock().info("Replay finished");
+        None
+    }
+
+    /// Returns true if the core is replaced
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        core: &mut Core<P, S>,
+        runner: &mut Runner<P, S>,
+    ) -> bool {
+        let mut replaced = false;
+        ui.horizontal(|ui| {
+            match self.mode {
+                Mode::Idle => {
+                    if ui
+                        .button("⏺ Record")
+                        .on_hover_text("Start a new journal from the current state")
+                        .clicked()
+                    {
+                        // the worker restarts from the recorded start
+                        runner.invalidate();
+                        self.journal = Journal {
+                            start: Some((&*core).into()),
+                            start_step: runner.cur_step(core),
+                            events: Vec::new(),
+                        };
+                        self.mode = Mode::Recording {
+                            params: None,
+                            noise: None,
+                            pending: None,
+                        };
+                    }
+                    if ui
+                        .button("▶ Replay")
+                        .on_hover_text(
+                            "Run the journal again from its start,\n\
+                            with its parameters instead of the ones of the panel",
+                        )
+                        .clicked()
+                    {
+                        replaced = self.start_replay(core, runner).notify_global().is_some();
+                    }
+                }
+                Mode::Recording { .. } => {
+                    if ui.button("⏹ Stop recording").clicked() {
+                        self.mode = Mode::Idle;
+                    }
+                }
+                Mode::Replaying { next, .. } => {
+                    ui.add(
+                        egui::ProgressBar::new(next as f32 / self.journal.events.len() as f32)
+                            .desired_width(80.),
+                    );
+                    if ui.button("⏹ Stop replay").clicked() {
+                        self.mode = Mode::Idle;
+                    }
+                }
+            }
+            ui.label(format!(
+                "{} events, {} steps",
+                self.journal.events.len(),
+                self.journal.steps()
+            ));
+        });
+        if matches!(self.mode, Mode::Idle) {
+            self.file
+                .show_save_load(ui, &mut self.journal)
+                .notify_global();
+        }
+        replaced
+    }
+}
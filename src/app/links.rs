@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, bail, ensure};
+use ui_traits::{Params, join_path};
+
+use crate::{expr::Expr, notify::ResultExt};
+
+/// Parameters defined as expressions of the others, like `pump = sqrt(alpha)*1.2`
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Links {
+    links: Vec<Link>,
+    /// enabled links in evaluation order, rebuilt after every edit
+    #[serde(skip)]
+    compiled: Option<Vec<Compiled>>,
+    /// why the link of a target was left out at the last evaluation
+    #[serde(skip)]
+    errors: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Link {
+    /// path of the linked parameter, see [`Params`]
+    target: String,
+    expr: String,
+    enabled: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Compiled {
+    target: String,
+    /// with the variables resolved to full paths
+    expr: Expr,
+    text: String,
+}
+
+/// Parse the enabled links and sort them so that every link comes after the ones it depends on.
+/// Invalid and cyclic links are left out and reported in the errors.
+fn compile(links: &[Link], paths: &[String]) -> (Vec<Compiled>, Vec<anyhow::Error>) {
+    let mut errors = Vec::new();
+    let mut parsed = Vec::new();
+    for l in links.iter().filter(|l| l.enabled) {
+        let r = (|| -> anyhow::Result<Compiled> {
+            ensure!(
+                paths.contains(&l.target),
+                "unknown parameter `{}`",
+                l.target
+            );
+            ensure!(
+                !parsed.iter().any(|c: &Compiled| c.target == l.target),
+                "`{}` is already linked",
+                l.target
+            );
+            let mut expr: Expr = l.expr.parse()?;
+            // `period` in the link of `disper.couple_decay` is `disper.period`
+            let parent = l.target.rsplit_once('.').map(|(p, _)| p);
+            expr.rename_vars(&mut |name| {
+                parent
+                    .map(|p| join_path(p, name))
+                    .filter(|path| paths.contains(path))
+                    .unwrap_or_else(|| name.to_string())
+            });
+            if let Some(name) = expr
+                .vars()
+                .into_iter()
+                .find(|v| !paths.iter().any(|p| p == v))
+            {
+                bail!("unknown parameter `{name}`");
+            }
+            Ok(Compiled {
+                target: l.target.clone(),
+                expr,
+                text: l.expr.clone(),
+            })
+        })()
+        .with_context(|| format!("Invalid link `{} = {}`", l.target, l.expr));
+        match r {
+            Ok(c) => parsed.push(c),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(parsed.len());
+    while !parsed.is_empty() {
+        let ready = parsed.iter().position(|c| {
+            c.expr
+                .vars()
+                .into_iter()
+                .all(|v| parsed.iter().all(|d| d.target != v))
+        });
+        match ready {
+            Some(i) => ordered.push(parsed.remove(i)),
+            None => {
+                let cycle = parsed
+                    .iter()
+                    .map(|c| c.target.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                errors.push(anyhow::anyhow!("Cyclic links between {cycle}"));
+                break;
+            }
+        }
+    }
+    (ordered, errors)
+}
+
+/// Evaluate the links in order, dependents seeing the new values in `values`.
+/// Returns the error of every link left out, the value of its target being kept.
+fn evaluate(compiled: &[Compiled], values: &mut HashMap<String, f64>) -> HashMap<String, String> {
+    let mut errors = HashMap::new();
+    for c in compiled {
+        match c.expr.eval(&|name| values.get(name).copied()) {
+            Ok(v) if v.is_finite() => {
+                values.insert(c.target.clone(), v);
+            }
+            Ok(v) => {
+                errors.insert(c.target.clone(), format!("Evaluates to {v}"));
+            }
+            Err(e) => {
+                errors.insert(c.target.clone(), format!("{e:#}"));
+            }
+        }
+    }
+    errors
+}
+
+impl Links {
+    /// Copy without the links of `paths`, with the targets of the enabled ones left out
+    pub(crate) fn without(&self, paths: &[String]) -> (Self, Vec<String>) {
+        let (links, left_out): (Vec<_>, Vec<_>) = self
+            .links
+            .iter()
+            .cloned()
+            .partition(|l| !paths.contains(&l.target));
+        let left_out = left_out
+            .into_iter()
+            .filter(|l| l.enabled)
+            .map(|l| l.target)
+            .collect();
+        (
+            Self {
+                links,
+                compiled: None,
+                errors: HashMap::new(),
+            },
+            left_out,
+        )
+    }
+
+    /// Write the linked values to `params`, marking them as driven.
+    /// The driven marks are expected to be cleared before.
+    pub(crate) fn apply(&mut self, params: &mut dyn Params) {
+        if self.links.is_empty() {
+            return;
+        }
+        let compiled = self.compiled.get_or_insert_with(|| {
+            let (compiled, errors) = compile(&self.links, &params.param_paths());
+            for e in errors {
+                Err::<(), _>(e).notify_global();
+            }
+            compiled
+        });
+
+        let mut values = HashMap::new();
+        params.visit_params("", &mut |path, param| {
+            values.insert(path.to_string(), param.get_f64());
+        });
+        let errors = evaluate(compiled, &mut values);
+        params.visit_params("", &mut |path, param| {
+            if let Some(c) = compiled
+                .iter()
+                .find(|c| c.target == path && !errors.contains_key(path))
+            {
+                param.set_f64(values[path]);
+                param.set_driven(Some(format!("Linked: {} = {}", c.target, c.text)));
+            }
+        });
+        self.errors = errors;
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, params: &mut dyn Params) {
+        ui.collapsing("Linked parameters", |ui| {
+            let paths = params.param_paths();
+            let mut changed = false;
+            let mut to_remove = None;
+            for (i, l) in self.links.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        changed |= ui.checkbox(&mut l.enabled, "").changed();
+                        egui::ComboBox::from_id_salt("parameter")
+                            .selected_text(l.target.as_str())
+                            .show_ui(ui, |ui| {
+                                for p in &paths {
+                                    changed |=
+                                        ui.selectable_value(&mut l.target, p.clone(), p).changed();
+                                }
+                            });
+                        ui.label("=");
+                        changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut l.expr)
+                                    .hint_text("sqrt(alpha)*1.2")
+                                    .desired_width(120.),
+                            )
+                            .lost_focus();
+                        if ui.button("🗑").clicked() {
+                            to_remove = Some(i);
+                        }
+                        if let Some(e) = self.errors.get(&l.target).filter(|_| l.enabled) {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                                .on_hover_text(e);
+                        }
+                    });
+                });
+            }
+            if let Some(i) = to_remove {
+                self.links.remove(i);
+                changed = true;
+            }
+            if ui
+                .button("➕")
+                .on_hover_text(
+                    "Define a parameter by an expression of the others,\n\
+                    parameters of the same group can be named without their prefix",
+                )
+                .clicked()
+                && let Some(path) = paths.first()
+            {
+                let value = params.get_param(path).unwrap_or_default();
+                self.links.push(Link {
+                    target: path.clone(),
+                    expr: value.to_string(),
+                    enabled: true,
+                });
+                changed = true;
+            }
+            if changed {
+                self.compiled = None;
+                self.errors.clear();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn link(target: &str, expr: &str) -> Link {
+        Link {
+            target: target.to_string(),
+            expr: expr.to_string(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn compile_links() {
+        let paths = ["alpha", "pump", "disper.period", "disper.couple_decay"].map(String::from);
+
+        let (compiled, errors) = compile(
+            &[
+                link("alpha", "2*disper.couple_decay"),
+                link("disper.couple_decay", "2*period"),
+                link("pump", "sqrt(beta)"),
+            ],
+            &paths,
+        );
+        assert_eq!(errors.len(), 1);
+        let targets = compiled
+            .iter()
+            .map(|c| c.target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["disper.couple_decay", "alpha"]);
+        assert_eq!(compiled[0].expr.vars(), ["disper.period"]);
+
+        let (compiled, errors) = compile(
+            &[
+                link("alpha", "pump"),
+                link("pump", "alpha + 1"),
+                link("disper.period", "3"),
+            ],
+            &paths,
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(compiled.len(), 1);
+
+        let links = Links {
+            links: vec![link("alpha", "pump"), link("disper.period", "3")],
+            ..Default::default()
+        };
+        let (kept, left_out) = links.without(&["alpha".to_string()]);
+        assert_eq!(kept.links, [link("disper.period", "3")]);
+        assert_eq!(left_out, ["alpha"]);
+    }
+
+    #[test]
+    fn skip_invalid_values() {
+        let paths = ["alpha", "pump", "disper.period"].map(String::from);
+        let (compiled, _) = compile(
+            &[
+                link("alpha", "sqrt(disper.period)"),
+                link("pump", "alpha + 1"),
+                link("disper.period", "-2"),
+            ],
+            &paths,
+        );
+        let mut values = paths.iter().map(|p| (p.clone(), 1.)).collect();
+        let errors = evaluate(&compiled, &mut values);
+        assert_eq!(errors.keys().collect::<Vec<_>>(), ["alpha"]);
+        assert_eq!(values["alpha"], 1.);
+        assert_eq!(values["pump"], 2.);
+        assert_eq!(values["disper.period"], -2.);
+    }
+}
@@ -1,28 +1,58 @@
+mod adaptive;
+mod automation;
+mod budget;
+mod comb;
+mod compare;
+mod convergence;
 mod core;
 mod dispersion;
+mod guard;
+mod history;
 mod impls;
+mod init_state;
+mod journal;
+mod links;
+mod phase_diagram;
+mod runner;
+mod seed;
 mod storage;
+mod tabs;
+mod triggers;
 
 pub use core::Core;
 use dispersion::ShowDispersion;
+use guard::Guard;
 pub use storage::CoreStorage;
 
 pub mod debugger;
 pub use debugger::Debugger;
 
+use budget::StepBudget;
+use comb::CombMetrics;
+use compare::Compare;
+use convergence::Convergence;
 use egui::{DragValue, Widget};
+use history::History;
+use init_state::InitEditor;
+use journal::Recorder;
+use phase_diagram::PhaseDiagram;
+use runner::Runner;
+use seed::Seed;
 use storage::GenAppStorage;
+use tabs::Workspace;
+use triggers::Triggers;
 
 use crate::{
     checkpoint,
+    construct::Model,
     controller::{Controller, SharedState, Simulator},
     file::{self, FileManager},
     notify::{ResultExt, TOASTS},
-    preview::{BasicPreviewTarget, PreviewTarget, Previewer},
+    preview::{ParamTarget, PreviewTarget, Previewer},
     util::{attractive_button, attractive_head},
     views::{ShowOn, State, Views, Visualizer},
 };
-pub struct GenApp<P, S, V, T = BasicPreviewTarget, D = ()>
+pub struct GenApp<P, S, V, T = ParamTarget, D = ()>
 where
     P: Controller<S>,
     S: Simulator,
@@ -34,6 +64,18 @@ where
     slider_len: Option<f32>,
     views: Views<V>,
     running: bool,
+    runner: Runner<P, S>,
+    budget: StepBudget,
+    history: History<P, S>,
+    recorder: Recorder<P, S>,
+    guard: Guard<P, S>,
+    convergence: Convergence,
+    compare: Compare<P, S>,
+    phase_diagram: PhaseDiagram,
+    comb: CombMetrics,
+    triggers: Triggers,
+    init_editor: InitEditor,
+    seed: Seed,
     profiler: bool,
     add_rand: bool,
     show_dispersion: ShowDispersion, //show, scale
@@ -43,9 +85,35 @@ where
     #[cfg(feature = "gpu")]
     render_state: eframe::egui_wgpu::RenderState,
     debugger: Option<D>,
+    model: Model,
+    switch_to: Option<Model>,
+    workspace: Workspace<P, S, V, T>,
 }
 
-const APP_NAME: &str = "LLE Simulator";
+/// Component types of a [`GenApp`], to reach them from the model aliases
+pub(crate) trait AppTypes {
+    type Controller;
+    type Simulator;
+}
+
+impl<P, S, V, T, D> AppTypes for GenApp<P, S, V, T, D>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S>,
+{
+    type Controller = P;
+    type Simulator = S;
+}
+
+pub(crate) const APP_NAME: &str = "LLE Simulator";
+
+/// Object-safe interface of [`GenApp`], so the model can be picked at runtime
+pub(crate) trait ModelApp: eframe::App {
+    /// Model requested in the start window, if any
+    fn take_switch(&mut self) -> Option<Model>;
+    fn open_start_window(&mut self);
+}
 
 impl<P, S, V, T, D> GenApp<P, S, V, T, D>
 where
@@ -58,15 +126,23 @@ where
         + for<'a> Visualizer<<S as SharedState<'a>>::SharedState>,
     T: PreviewTarget<P, S> + for<'a> serde::Deserialize<'a> + Default,
 {
-    /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // This is also where you can customized the look at feel of egui using
-        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
-
+    /// Called before the first frame of the model.
+    pub fn new(
+        model: Model,
+        storage: Option<&dyn eframe::Storage>,
+        #[cfg(feature = "gpu")] render_state: eframe::egui_wgpu::RenderState,
+    ) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        let c: GenAppStorage<P, S, V, T> = cc.storage.map_or_else(Default::default, |e| {
-            eframe::get_value(e, APP_NAME).unwrap_or_default()
+        let c: GenAppStorage<P, S, V, T> = storage.map_or_else(Default::default, |e| {
+            eframe::get_value(e, &model.storage_key())
+                .or_else(|| {
+                    // keep the state of older versions until it's saved under the new key
+                    model
+                        .legacy_storage_key()
+                        .and_then(|key| eframe::get_value(e, key))
+                })
+                .unwrap_or_default()
         });
 
         GenApp {
@@ -76,6 +152,18 @@ where
             slider_len: c.slider_len,
             views: c.views,
             running: c.running,
+            runner: c.runner,
+            budget: c.budget,
+            history: c.history,
+            recorder: c.recorder,
+            guard: c.guard,
+            convergence: c.convergence,
+            compare: c.compare,
+            phase_diagram: c.phase_diagram,
+            comb: c.comb,
+            triggers: c.triggers,
+            init_editor: c.init_editor,
+            seed: c.seed,
             profiler: c.profiler,
             add_rand: c.add_rand,
             file_state: c.file_state,
@@ -83,8 +171,11 @@ where
             show_dispersion: c.show_dispersion,
             check_points: c.check_points.clone(),
             #[cfg(feature = "gpu")]
-            render_state: cc.wgpu_render_state.clone().unwrap(),
+            render_state,
             debugger: None,
+            model,
+            switch_to: None,
+            workspace: Workspace::from_storage(c.tabs, c.active_tab),
         }
     }
 }
@@ -110,11 +201,13 @@ where
         if !self.is_init {
             return;
         }
+        self.show_tabs(ctx);
         let play_control = self.control_panel(ctx);
 
         let refresh = self.run_simulation(play_control);
+        let background = self.run_background_tabs();
 
-        if refresh {
+        if refresh || background || self.runner.is_busy() {
             ctx.request_repaint();
         }
 
@@ -123,6 +216,7 @@ where
 
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let (tabs, active_tab) = self.workspace.to_storage();
         let state = GenAppStorage {
             core: (&self.core).into(),
             scout: self.scout.clone_for_save(),
@@ -130,13 +224,49 @@ where
             slider_len: self.slider_len,
             views: self.views.clone(),
             running: self.running,
+            runner: self.runner.clone_for_save(),
+            budget: self.budget.clone(),
+            history: self.history.clone_for_save(),
+            recorder: self.recorder.clone_for_save(),
+            guard: self.guard.clone_for_save(),
+            convergence: self.convergence.clone_for_save(),
+            compare: self.compare.clone_for_save(),
+            phase_diagram: self.phase_diagram.clone_for_save(),
+            comb: self.comb.clone_for_save(),
+            triggers: self.triggers.clone(),
+            init_editor: self.init_editor.clone(),
+            seed: self.seed.clone(),
             profiler: self.profiler,
             add_rand: self.add_rand,
             check_points: self.check_points.clone(),
             show_dispersion: self.show_dispersion.clone(),
             file_state: self.file_state.clone_for_save(),
             file_checkpoints: self.file_checkpoints.clone_for_save(),
+            tabs,
+            active_tab,
         };
-        eframe::set_value(storage, APP_NAME, &state);
+        eframe::set_value(storage, &self.model.storage_key(), &state);
+    }
+}
+
+impl<P, S, V, T, D> ModelApp for GenApp<P, S, V, T, D>
+where
+    P: Default + Clone + Controller<S> + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+    for<'a> <S as SharedState<'a>>::SharedState: State<OwnedState = S::OwnedState>,
+    T: PreviewTarget<P, S> + serde::Serialize + for<'a> serde::Deserialize<'a> + Default + Clone,
+    Views<V>: Default
+        + for<'a> Visualizer<<S as SharedState<'a>>::SharedState>
+        + serde::Serialize
+        + for<'a> serde::Deserialize<'a>
+        + Clone,
+    D: for<'a> Debugger<<S as SharedState<'a>>::SharedState> + Default,
+{
+    fn take_switch(&mut self) -> Option<Model> {
+        self.switch_to.take()
+    }
+
+    fn open_start_window(&mut self) {
+        self.is_init = false;
     }
 }
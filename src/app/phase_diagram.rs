@@ -0,0 +1,628 @@
+use std::{
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::ensure;
+use lle::num_complex::Complex64;
+use rayon::prelude::*;
+use ui_traits::{ControllerUI, DisplayStr, Params};
+
+use crate::{
+    controller::{Components, Controller, Simulator, StoreState},
+    drawer::{ColorMapDrawer, DrawMat},
+    notify::ResultExt,
+    preview::{LegacyTarget, ParamTarget, PreviewTarget},
+    util::{Promise, try_poll},
+};
+
+use super::{Core, seed::Seed, triggers::count_peaks};
+
+/// Samples of the field over the observation window
+const SAMPLES: u32 = 16;
+/// Spread of `|ψ|²` relative to its mean under which the field is homogeneous
+const FLAT: f64 = 1E-2;
+/// Relative change of the energy and of the peak power over the window
+/// under which the field is stationary
+const STEADY: f64 = 1E-3;
+/// Fraction of the domain above half the peak under which the peaks are localized
+const LOCALIZED: f64 = 0.2;
+
+/// Regime of the field at the end of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    /// turned NaN or infinite
+    Diverged,
+    /// homogeneous
+    Cw,
+    /// stationary pattern filling the domain
+    Turing,
+    /// modulation instability not settling
+    Chaos,
+    /// localized peaks of fixed count, oscillating
+    Breather(u32),
+    /// localized peaks, stationary up to a drift
+    Solitons(u32),
+}
+
+impl Phase {
+    /// Value drawn on the map, the soliton count added to the last one
+    fn code(&self) -> u32 {
+        match *self {
+            Phase::Diverged => 0,
+            Phase::Cw => 1,
+            Phase::Turing => 2,
+            Phase::Chaos => 3,
+            Phase::Breather(_) => 4,
+            Phase::Solitons(n) => 4 + n,
+        }
+    }
+
+    fn count(&self) -> u32 {
+        match *self {
+            Phase::Breather(n) | Phase::Solitons(n) => n,
+            _ => 0,
+        }
+    }
+
+    /// Name in the csv
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Diverged => "diverged",
+            Phase::Cw => "cw",
+            Phase::Turing => "turing",
+            Phase::Chaos => "chaotic_mi",
+            Phase::Breather(_) => "breather",
+            Phase::Solitons(_) => "solitons",
+        }
+    }
+
+    fn desc(&self) -> String {
+        match *self {
+            Phase::Diverged => "Diverged".to_string(),
+            Phase::Cw => "CW".to_string(),
+            Phase::Turing => "Turing pattern".to_string(),
+            Phase::Chaos => "Chaotic MI".to_string(),
+            Phase::Breather(n) => format!("Breather ({n} peaks)"),
+            Phase::Solitons(n) => format!("{n} solitons"),
+        }
+    }
+}
+
+/// Summary of one sample of `|ψ|²`
+struct Profile {
+    mean: f64,
+    max: f64,
+    /// `(max - min) / mean`
+    spread: f64,
+    /// peaks above half the way from the minimum to the maximum
+    peaks: u32,
+    /// fraction of the domain above that level
+    above: f64,
+}
+
+impl Profile {
+    fn new(field: &[Complex64]) -> Self {
+        let n = field.len().max(1) as f64;
+        let powers = || field.iter().map(|x| x.norm_sqr());
+        let (min, max) = powers().fold((f64::INFINITY, 0f64), |(lo, hi), p| (lo.min(p), hi.max(p)));
+        let min = min.min(max);
+        let mean = powers().sum::<f64>() / n;
+        let half = (min + max) / 2.;
+        Self {
+            mean,
+            max,
+            spread: if mean > 0. { (max - min) / mean } else { 0. },
+            peaks: count_peaks(field, half) as u32,
+            above: powers().filter(|&p| p > half).count() as f64 / n,
+        }
+    }
+}
+
+/// Regime from samples of the field over the observation window, the oldest first
+fn classify(samples: &[Vec<Complex64>]) -> Phase {
+    if samples.iter().flatten().any(|x| !x.is_finite()) {
+        return Phase::Diverged;
+    }
+    let profiles = samples.iter().map(|s| Profile::new(s)).collect::<Vec<_>>();
+    let Some(last) = profiles.last() else {
+        return Phase::Cw;
+    };
+    if profiles.iter().all(|p| p.spread < FLAT) {
+        return Phase::Cw;
+    }
+    let change = |f: fn(&Profile) -> f64| {
+        let (lo, hi) = profiles
+            .iter()
+            .map(f)
+            .fold((f64::INFINITY, 0f64), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        if hi > 0. { (hi - lo) / hi } else { 0. }
+    };
+    let steady = change(|p| p.mean) < STEADY && change(|p| p.max) < STEADY;
+    let localized = last.above < LOCALIZED;
+    let same_count = profiles.iter().all(|p| p.peaks == last.peaks);
+    match (steady, localized) {
+        (true, true) => Phase::Solitons(last.peaks),
+        (true, false) => Phase::Turing,
+        (false, true) if same_count => Phase::Breather(last.peaks),
+        _ => Phase::Chaos,
+    }
+}
+
+/// Run a fresh core from `seed` for `steps` steps, sampling the last `window` ones
+fn run_point<P, S>(mut core: Core<P, S>, seed: &Seed, steps: u32, window: u32) -> Phase
+where
+    P: Controller<S>,
+    S: Simulator,
+{
+    if seed.apply(&mut core).is_err() {
+        core.add_random();
+    }
+    core.sync_paras();
+    let window = window.min(steps);
+    core.simulator.run(steps - window);
+    let every = (window / SAMPLES).max(1);
+    let mut samples = Vec::new();
+    for _ in 0..SAMPLES {
+        core.simulator.run(every);
+        let mut state = core.simulator.get_owned_state();
+        // the first component, as the signal of the coupled models
+        let field = state
+            .components_mut()
+            .into_iter()
+            .next()
+            .map(|f| f.to_vec())
+            .unwrap_or_default();
+        let diverged = field.iter().any(|x| !x.is_finite());
+        samples.push(field);
+        if diverged {
+            break;
+        }
+    }
+    classify(&samples)
+}
+
+/// Values of a swept parameter, evenly spaced from `from` to `to`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Axis {
+    target: ParamTarget,
+    from: f64,
+    to: f64,
+    count: usize,
+}
+
+impl Axis {
+    fn values(&self) -> Vec<f64> {
+        let step = if self.count > 1 {
+            (self.to - self.from) / (self.count - 1) as f64
+        } else {
+            0.
+        };
+        (0..self.count)
+            .map(|i| self.from + step * i as f64)
+            .collect()
+    }
+
+    fn show<P: Controller<S> + Clone, S: Simulator>(
+        &mut self,
+        ui: &mut egui::Ui,
+        paths: &[String],
+    ) {
+        PreviewTarget::<P, S>::show_target(&mut self.target, ui, paths);
+        ui.add(
+            egui::DragValue::new(&mut self.from)
+                .speed(0.1)
+                .prefix("from "),
+        );
+        ui.add(egui::DragValue::new(&mut self.to).speed(0.1).prefix("to "));
+        ui.add(
+            egui::DragValue::new(&mut self.count)
+                .range(2..=256)
+                .prefix("× "),
+        );
+    }
+}
+
+/// Regimes over the grid, row by row along `y`
+struct Map {
+    x: (String, Vec<f64>),
+    y: (String, Vec<f64>),
+    phases: Vec<Phase>,
+}
+
+impl Map {
+    fn csv(&self) -> String {
+        let mut csv = format!("{},{},phase,count,code\n", self.x.0, self.y.0);
+        let points = self
+            .y
+            .1
+            .iter()
+            .flat_map(|y| self.x.1.iter().map(move |x| (x, y)));
+        for ((x, y), p) in points.zip(&self.phases) {
+            let _ = writeln!(csv, "{x},{y},{},{},{}", p.name(), p.count(), p.code());
+        }
+        csv
+    }
+}
+
+struct Job {
+    promise: Option<Promise<Option<Vec<Phase>>>>,
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    map: Map,
+}
+
+/// Sweeps two parameters over a grid, running a fresh core from a seed at every point
+/// and classifying the state it ends in
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct PhaseDiagram {
+    pub(crate) open: bool,
+    x: Axis,
+    y: Axis,
+    /// steps run at every point, the last `window` ones being observed
+    steps: u32,
+    window: u32,
+    seed: Seed,
+    #[serde(skip)]
+    job: Option<Job>,
+    #[serde(skip)]
+    map: Option<Map>,
+    #[serde(skip)]
+    drawer: Option<ColorMapDrawer>,
+    #[serde(skip)]
+    export: Option<Promise<anyhow::Result<()>>>,
+}
+
+impl Default for PhaseDiagram {
+    fn default() -> Self {
+        Self {
+            open: false,
+            x: Axis {
+                target: ParamTarget::of(LegacyTarget::Alpha),
+                from: -8.,
+                to: 8.,
+                count: 16,
+            },
+            y: Axis {
+                target: ParamTarget::of(LegacyTarget::Pump),
+                from: 0.5,
+                to: 5.,
+                count: 16,
+            },
+            steps: 20_000,
+            window: 4_000,
+            seed: Seed::default(),
+            job: None,
+            map: None,
+            drawer: None,
+            export: None,
+        }
+    }
+}
+
+impl PhaseDiagram {
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            open: self.open,
+            x: self.x.clone(),
+            y: self.y.clone(),
+            steps: self.steps,
+            window: self.window,
+            seed: self.seed.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Start the sweep from the parameters, the noise and the links of `core`
+    fn start<P, S>(&mut self, core: &Core<P, S>) -> anyhow::Result<()>
+    where
+        P: Controller<S> + Clone,
+        S: Simulator,
+    {
+        let mut controller = core.controller.clone();
+        let mut resolve = |axis: &Axis| -> anyhow::Result<Vec<String>> {
+            let paths = axis.target.resolve_all::<P, S>(&mut controller);
+            ensure!(
+                !paths.is_empty(),
+                "The model has no parameter `{}`",
+                axis.target.desc()
+            );
+            Ok(paths)
+        };
+        let (x_paths, y_paths) = (resolve(&self.x)?, resolve(&self.y)?);
+        ensure!(
+            !x_paths.iter().any(|p| y_paths.contains(p)),
+            "Sweep two different parameters"
+        );
+        // a link would overwrite the swept values
+        let swept = [&x_paths[..], &y_paths[..]].concat();
+        let (links, left_out) = core.links.without(&swept);
+        if !left_out.is_empty() {
+            crate::notify::TOASTS.lock().warning(format!(
+                "The links of {} are left out of the sweep",
+                left_out.join(", ")
+            ));
+        }
+        let (xs, ys) = (self.x.values(), self.y.values());
+        let points = ys
+            .iter()
+            .flat_map(|&y| xs.iter().map(move |&x| (x, y)))
+            .collect::<Vec<_>>();
+
+        let done = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (dim, random) = (core.dim, core.random.clone());
+        let (seed, steps, window) = (self.seed.clone(), self.steps, self.window);
+        let task = {
+            let (done, cancel) = (done.clone(), cancel.clone());
+            let (x_paths, y_paths) = (x_paths.clone(), y_paths.clone());
+            move || -> Option<Vec<Phase>> {
+                points
+                    .par_iter()
+                    .map(|&(x, y)| {
+                        if cancel.load(Ordering::Relaxed) {
+                            return None;
+                        }
+                        let mut controller = controller.clone();
+                        for p in &x_paths {
+                            controller.set_param(p, x);
+                        }
+                        for p in &y_paths {
+                            controller.set_param(p, y);
+                        }
+                        let core = Core {
+                            dim,
+                            simulator: controller.construct_engine(dim),
+                            controller,
+                            random: random.clone(),
+                            automation: Default::default(),
+                            links: links.clone(),
+                        };
+                        let phase = run_point(core, &seed, steps, window);
+                        done.fetch_add(1, Ordering::Relaxed);
+                        Some(phase)
+                    })
+                    .collect()
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let promise = Promise::new_thread("phase_diagram", task);
+        #[cfg(target_arch = "wasm32")]
+        let promise = Promise::new_web("phase_diagram", task);
+        self.job = Some(Job {
+            promise: Some(promise),
+            done,
+            cancel,
+            map: Map {
+                x: (x_paths.join("+"), xs),
+                y: (y_paths.join("+"), ys),
+                phases: Vec::new(),
+            },
+        });
+        Ok(())
+    }
+
+    fn poll(&mut self) {
+        if let Some(r) = try_poll(&mut self.export) {
+            r.notify_global();
+        }
+        let Some(job) = self.job.as_mut() else {
+            return;
+        };
+        let Some(phases) = try_poll(&mut job.promise) else {
+            return;
+        };
+        let Some(mut job) = self.job.take() else {
+            return;
+        };
+        match phases {
+            Some(phases) => {
+                job.map.phases = phases;
+                self.map = Some(job.map);
+                self.drawer = None;
+            }
+            None => {
+                crate::notify::TOASTS.lock().info("Phase diagram cancelled");
+            }
+        }
+    }
+
+    fn export(&mut self) {
+        let Some(map) = &self.map else {
+            return;
+        };
+        let csv = map.csv();
+        self.export = Some(Promise::new(async move {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("csv", &["csv"])
+                .set_file_name("phase_diagram.csv")
+                .save_file()
+                .await;
+            if let Some(file) = file {
+                file.write(csv.as_bytes()).await?;
+            }
+            Ok(())
+        }));
+    }
+
+    pub(crate) fn show<P, S>(
+        &mut self,
+        ctx: &egui::Context,
+        core: &Core<P, S>,
+        #[cfg(feature = "gpu")] render_state: &eframe::egui_wgpu::RenderState,
+    ) where
+        P: Controller<S> + Clone,
+        S: Simulator,
+    {
+        self.poll();
+        if self.is_running() {
+            ctx.request_repaint();
+        }
+        let mut open = self.open;
+        egui::Window::new("Phase diagram")
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                let paths = core.controller.clone().param_paths();
+                egui::Grid::new("phase diagram axes").show(ui, |ui| {
+                    ui.label("x");
+                    ui.push_id("x", |ui| {
+                        ui.horizontal(|ui| self.x.show::<P, S>(ui, &paths));
+                    });
+                    ui.end_row();
+                    ui.label("y");
+                    ui.push_id("y", |ui| {
+                        ui.horizontal(|ui| self.y.show::<P, S>(ui, &paths));
+                    });
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.steps)
+                            .range(1..=u32::MAX)
+                            .prefix("steps "),
+                    )
+                    .on_hover_text("Steps run at every point");
+                    ui.add(
+                        egui::DragValue::new(&mut self.window)
+                            .range(SAMPLES..=self.steps.max(SAMPLES))
+                            .prefix("observed "),
+                    )
+                    .on_hover_text("Last steps sampled to classify the dynamics");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    self.seed.show_controller(ui);
+                });
+
+                if let Some(job) = &self.job {
+                    let total = job.map.x.1.len() * job.map.y.1.len();
+                    let done = job.done.load(Ordering::Relaxed);
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                                .text(format!("{done}/{total}"))
+                                .desired_width(200.),
+                        );
+                        if ui.button("Cancel").clicked() {
+                            job.cancel.store(true, Ordering::Relaxed);
+                        }
+                    });
+                } else if ui
+                    .button("▶ Run")
+                    .on_hover_text(
+                        "Run every point from the seed with the noise and the links of the model,\n\
+                        the schedules left out",
+                    )
+                    .clicked()
+                {
+                    self.start(core).notify_global();
+                }
+
+                let Some(map) = &self.map else {
+                    return;
+                };
+                ui.separator();
+                let (w, h) = (map.x.1.len(), map.y.1.len());
+                let first = |v: &[f64]| v.first().copied().unwrap_or_default();
+                let last = |v: &[f64]| v.last().copied().unwrap_or_default();
+                ui.label(format!(
+                    "{}: {} → {} left to right, {}: {} → {} bottom to top",
+                    map.x.0,
+                    first(&map.x.1),
+                    last(&map.x.1),
+                    map.y.0,
+                    first(&map.y.1),
+                    last(&map.y.1),
+                ));
+                let created = self.drawer.is_none();
+                let drawer = self.drawer.get_or_insert_with(|| {
+                    #[cfg(not(feature = "gpu"))]
+                    {
+                        ColorMapDrawer::default()
+                    }
+                    #[cfg(feature = "gpu")]
+                    {
+                        ColorMapDrawer::new("Phase diagram", w as _, h as _, render_state)
+                    }
+                });
+                if created {
+                    let codes = map
+                        .phases
+                        .iter()
+                        .map(|p| p.code() as f32)
+                        .collect::<Vec<_>>();
+                    let max = codes.iter().copied().fold(1., f32::max);
+                    drawer.set_matrix(w, h, &codes, Some([0., max]));
+                }
+                let (_id, rect) =
+                    ui.allocate_space(egui::vec2(ui.available_width().max(300.), 300.));
+                let mut cui = ui.new_child(
+                    egui::UiBuilder::default()
+                        .max_rect(rect)
+                        .layout(*ui.layout()),
+                );
+                drawer
+                    .draw_mat_on_ui(w, &mut cui)
+                    .expect("can't plot colormap");
+
+                let mut phases = map.phases.clone();
+                phases.sort_by_key(Phase::code);
+                phases.dedup();
+                for p in phases {
+                    let n = map.phases.iter().filter(|x| **x == p).count();
+                    ui.label(format!("{}: {} ({n} points)", p.code(), p.desc()));
+                }
+                if ui.button("💾 Export CSV").clicked() {
+                    self.export();
+                }
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::TAU;
+
+    use super::*;
+
+    fn field(f: impl Fn(f64) -> f64) -> Vec<Complex64> {
+        let n = 256;
+        (0..n)
+            .map(|j| Complex64::new(f(TAU * j as f64 / n as f64).sqrt(), 0.))
+            .collect()
+    }
+
+    #[test]
+    fn phases() {
+        let sech2 = |x: f64| 1. / (8. * x).cosh().powi(2);
+        let solitons = |amp: f64| field(move |t| 0.5 + amp * (sech2(t - 2.) + sech2(t - 4.)));
+        let cw = vec![field(|_| 1.); 4];
+        assert_eq!(classify(&cw), Phase::Cw);
+
+        assert_eq!(classify(&vec![solitons(4.); 4]), Phase::Solitons(2));
+        let breather = (0..8)
+            .map(|i| solitons(4. + (i as f64).sin()))
+            .collect::<Vec<_>>();
+        assert_eq!(classify(&breather), Phase::Breather(2));
+
+        let rolls = vec![field(|t| 1. + (5. * t).cos()); 4];
+        assert_eq!(classify(&rolls), Phase::Turing);
+        let chaos = (1..5)
+            .map(|i| field(move |t| 1. + (i as f64 * 3. * t).cos().powi(2) * i as f64))
+            .collect::<Vec<_>>();
+        assert_eq!(classify(&chaos), Phase::Chaos);
+
+        let mut nan = solitons(4.);
+        nan[3].re = f64::NAN;
+        assert_eq!(classify(&[solitons(4.), nan]), Phase::Diverged);
+    }
+}
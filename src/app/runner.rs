@@ -0,0 +1,267 @@
+use std::{sync::Arc, time::Duration};
+
+use egui::mutex::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use crate::{
+    controller::{Controller, Simulator, StoreState},
+    random::RandomNoise,
+    util::{Promise, try_poll},
+};
+
+use super::{Core, CoreStorage, adaptive::AdaptiveStep};
+
+/// Runs the batches of steps of the main [`Core`], on a worker thread if `background` is set.
+///
+/// In the background, the worker keeps its own copy of the core: the controller is sent to it
+/// before every batch and the state is sent back after, so the UI never waits for the solver.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct Runner<C, S: StoreState> {
+    #[serde(default = "default_background")]
+    pub(crate) background: bool,
+    #[serde(default)]
+    pub(crate) adaptive: AdaptiveStep,
+    #[serde(skip)]
+    worker: Option<Arc<Mutex<Worker<C, S>>>>,
+    /// trial copy of the solver for the adaptive step, see [`AdaptiveStep::run`]
+    #[serde(skip)]
+    trial: Option<S>,
+    #[serde(skip)]
+    promise: Option<Promise<Snapshot<S>>>,
+    /// steps the solver of the main core doesn't count: run by the worker,
+    /// or before the main core was replaced by a copy
+    #[serde(skip)]
+    extra_steps: u32,
+}
+
+fn default_background() -> bool {
+    cfg!(not(target_arch = "wasm32"))
+}
+
+/// Copy of the main core run in the background, with its own trial solver
+struct Worker<C, S> {
+    core: Core<C, S>,
+    trial: Option<S>,
+}
+
+/// Sent back by the worker after a batch
+struct Snapshot<S: StoreState> {
+    state: S::OwnedState,
+    random: RandomNoise,
+    adaptive: AdaptiveStep,
+    batch: Batch,
+}
+
+/// A finished batch of steps
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Batch {
+    pub(crate) steps: u32,
+    /// solver time
+    pub(crate) elapsed: Duration,
+    /// `Δt` the batch ran with
+    pub(crate) step_dist: f64,
+}
+
+impl Batch {
+    fn run<C: Controller<S>, S: Simulator>(
+        core: &mut Core<C, S>,
+        adaptive: &mut AdaptiveStep,
+        trial: &mut Option<S>,
+        steps: u32,
+        step_dist: Option<f64>,
+    ) -> Self {
+        let start = Instant::now();
+        if let Some(h) = step_dist {
+            core.simulator.set_step_dist(h);
+            core.simulator.run(steps);
+        } else {
+            adaptive.run(core, trial, steps);
+        }
+        Self {
+            steps,
+            elapsed: start.elapsed(),
+            step_dist: core.simulator.step_dist(),
+        }
+    }
+}
+
+impl<C, S: StoreState> Default for Runner<C, S> {
+    fn default() -> Self {
+        Self {
+            background: default_background(),
+            adaptive: AdaptiveStep::default(),
+            worker: None,
+            trial: None,
+            promise: None,
+            extra_steps: 0,
+        }
+    }
+}
+
+impl<C, S> Runner<C, S>
+where
+    C: Controller<S> + Clone,
+    S: Simulator,
+{
+    pub(crate) fn clone_for_save(&self) -> Self {
+        Self {
+            background: self.background,
+            adaptive: self.adaptive,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .toggle_value(&mut self.background, "Background")
+                .on_hover_text("Run the simulation on a worker thread,\nkeeping the UI responsive")
+                .changed()
+            {
+                // the main core is up to date with the worker, keep counting its steps
+                self.worker = None;
+                self.promise = None;
+            }
+            if self.is_busy() {
+                ui.spinner();
+            }
+        });
+        #[cfg(target_arch = "wasm32")]
+        if self.background {
+            crate::util::warn_single_thread(ui);
+        }
+    }
+
+    /// A batch is running on the worker
+    pub(crate) fn is_busy(&self) -> bool {
+        self.promise.is_some()
+    }
+
+    /// The next call to [`Self::step`] runs a batch
+    pub(crate) fn is_ready(&self) -> bool {
+        !self.background || !self.is_busy()
+    }
+
+    /// Drop the worker copy, to be called whenever the state of the main core is set.
+    /// The next batch starts over from the main core, the step count goes on.
+    pub(crate) fn invalidate(&mut self) {
+        self.worker = None;
+        self.trial = None;
+        self.promise = None;
+    }
+
+    /// Drop the worker copy and count the steps from the ones of `core`,
+    /// when it's refreshed, reset or loaded
+    pub(crate) fn restart(&mut self) {
+        self.invalidate();
+        self.extra_steps = 0;
+    }
+
+    /// Drop the worker copy and go on counting from `step`,
+    /// when the main core is replaced by a copy whose solver starts over
+    pub(crate) fn continue_at(&mut self, step: u32, core: &Core<C, S>) {
+        self.invalidate();
+        self.extra_steps = step.saturating_sub(core.simulator.cur_step());
+    }
+
+    /// Current step of the simulation, counting the steps run by the worker
+    pub(crate) fn cur_step(&self, core: &Core<C, S>) -> u32 {
+        core.simulator.cur_step().saturating_add(self.extra_steps)
+    }
+
+    /// Copy back the result of the last background batch to `core`, if it's finished
+    pub(crate) fn poll(&mut self, core: &mut Core<C, S>) -> Option<Batch> {
+        let snapshot = try_poll(&mut self.promise)?;
+        puffin_egui::puffin::profile_function!();
+        core.simulator.set_owned_state(snapshot.state);
+        let std_dev = core.random.std_dev();
+        core.random = snapshot.random;
+        core.random.set_std_dev(std_dev);
+        self.adaptive.update(&snapshot.adaptive);
+        self.extra_steps += snapshot.batch.steps;
+        Some(snapshot.batch)
+    }
+
+    /// Run a batch of `steps` steps on `core`, or send it to the worker if it's idle.
+    /// `step_dist` sets the `Δt` of a replayed batch instead of adapting it.
+    /// Returns the batch if it's run in place.
+    pub(crate) fn step(
+        &mut self,
+        core: &mut Core<C, S>,
+        steps: u32,
+        add_rand: bool,
+        step_dist: Option<f64>,
+    ) -> Option<Batch> {
+        if !self.background {
+            if add_rand {
+                puffin_egui::puffin::profile_scope!("add random");
+                core.add_random();
+            }
+            return Some(Batch::run(
+                core,
+                &mut self.adaptive,
+                &mut self.trial,
+                steps,
+                step_dist,
+            ));
+        }
+        if !self.is_busy() {
+            self.dispatch(core, steps, add_rand, step_dist);
+        }
+        None
+    }
+
+    fn dispatch(&mut self, core: &Core<C, S>, steps: u32, add_rand: bool, step_dist: Option<f64>) {
+        let worker = self
+            .worker
+            .get_or_insert_with(|| {
+                Arc::new(Mutex::new(Worker {
+                    core: CoreStorage::from(core).into(),
+                    trial: None,
+                }))
+            })
+            .clone();
+        let controller = core.controller.clone();
+        let std_dev = core.random.std_dev();
+        let mut adaptive = self.adaptive;
+        let task = move || {
+            let mut guard = worker.lock();
+            let Worker {
+                core: worker,
+                trial,
+            } = &mut *guard;
+            worker.controller = controller;
+            worker.random.set_std_dev(std_dev);
+            // the controller is already scheduled by the main core
+            let Core {
+                controller,
+                simulator,
+                ..
+            } = worker;
+            controller.sync_paras(simulator);
+            if add_rand {
+                worker.add_random();
+            }
+            let batch = Batch::run(worker, &mut adaptive, trial, steps, step_dist);
+            Snapshot {
+                state: worker.simulator.get_owned_state(),
+                random: worker.random.clone(),
+                adaptive,
+                batch,
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.promise = Some(Promise::new_thread("simulation", task));
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.promise = Some(Promise::new_web("simulation", task));
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use anyhow::{Context, ensure};
+use lle::num_complex::Complex64;
+use ui_traits::{ControllerUI, DisplayStr, Params};
+
+use crate::controller::{Components, Controller, Simulator, StoreState};
+
+use super::{Core, init_state::theta};
+
+/// Analytic initial states of the LLE as the solver integrates it,
+/// `∂ψ/∂t = -(1 + iα)ψ - i|ψ|²ψ + iβ/2 ∂²ψ/∂θ² + F`,
+/// from the parameters of the current controller.
+/// It's the complex conjugate of the usual form, whose detuning is `-α`:
+/// solitons need `α < 0` and `β < 0`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Seed {
+    kind: SeedKind,
+    /// number of solitons of [`SeedKind::Solitons`]
+    solitons: u32,
+    background: Branch,
+}
+
+impl Default for Seed {
+    fn default() -> Self {
+        Self {
+            kind: SeedKind::Noise,
+            solitons: 2,
+            background: Branch::Upper,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, enum_iterator::Sequence,
+)]
+enum SeedKind {
+    /// zero field, as the 🔄 refresh
+    Noise,
+    /// homogeneous steady state
+    Cw,
+    Soliton,
+    Solitons,
+    /// roll at the most unstable modulation instability wavenumber
+    Turing,
+}
+
+impl DisplayStr for SeedKind {
+    fn desc(&self) -> &str {
+        match self {
+            SeedKind::Noise => "Noise",
+            SeedKind::Cw => "CW steady state",
+            SeedKind::Soliton => "Soliton",
+            SeedKind::Solitons => "N solitons",
+            SeedKind::Turing => "Turing roll",
+        }
+    }
+}
+
+/// Branch of the homogeneous steady states
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, enum_iterator::Sequence,
+)]
+enum Branch {
+    Upper,
+    Lower,
+}
+
+impl DisplayStr for Branch {
+    fn desc(&self) -> &str {
+        match self {
+            Branch::Upper => "Upper",
+            Branch::Lower => "Lower",
+        }
+    }
+}
+
+/// Normalized parameters of the LLE
+#[derive(Debug, Clone, Copy)]
+struct Lle {
+    alpha: f64,
+    pump: f64,
+    beta: f64,
+}
+
+impl Lle {
+    /// Look for the parameters among the ones of the controller,
+    /// nested as in `basic.alpha` or named after the pump as in `pump.peak`
+    fn from_params(params: &mut dyn Params) -> anyhow::Result<Self> {
+        let paths = params.param_paths();
+        let mut find = |names: &[&str]| -> anyhow::Result<f64> {
+            let path = names
+                .iter()
+                .find_map(|name| {
+                    paths
+                        .iter()
+                        .find(|p| *p == name || p.ends_with(&format!(".{name}")))
+                })
+                .with_context(|| format!("The model has no parameter `{}`", names[0]))?;
+            Ok(params.get_param(path).unwrap_or_default())
+        };
+        Ok(Self {
+            alpha: find(&["alpha"])?,
+            pump: find(&[
+                "pump",
+                "pump.amplitude",
+                "pump.peak",
+                "pump.const_pump",
+                "pump.pulse1.peak",
+            ])?,
+            beta: find(&["linear"])?,
+        })
+    }
+
+    /// Powers `|ψ|²` of the homogeneous steady states, ascending.
+    /// They are the real roots of `ρ³ + 2αρ² + (1 + α²)ρ - F² = 0`
+    fn cw_powers(&self) -> Vec<f64> {
+        let Self { alpha, pump, .. } = *self;
+        let (b, c, d) = (2. * alpha, 1. + alpha * alpha, -pump * pump);
+        // depressed cubic t³ + pt + q = 0, with ρ = t - b/3
+        let p = c - b * b / 3.;
+        let q = 2. * b.powi(3) / 27. - b * c / 3. + d;
+        let shift = -b / 3.;
+        let disc = q * q / 4. + p.powi(3) / 27.;
+        let mut roots = if disc < 0. {
+            let r = 2. * (-p / 3.).sqrt();
+            let phi = (3. * q / (2. * p) * (-3. / p).sqrt()).clamp(-1., 1.).acos() / 3.;
+            (0..3)
+                .map(|k| r * (phi - TAU * k as f64 / 3.).cos() + shift)
+                .collect::<Vec<_>>()
+        } else {
+            let s = disc.sqrt();
+            vec![(-q / 2. + s).cbrt() + (-q / 2. - s).cbrt() + shift]
+        };
+        roots.sort_by(f64::total_cmp);
+        roots
+    }
+
+    /// Homogeneous steady state `ψ₀ = F / (1 + i(α + ρ))` on `branch`, with its power
+    fn cw(&self, branch: Branch) -> (Complex64, f64) {
+        let powers = self.cw_powers();
+        let rho = match branch {
+            Branch::Upper => powers[powers.len() - 1],
+            Branch::Lower => powers[0],
+        };
+        let psi = self.pump / Complex64::new(1., self.alpha + rho);
+        (psi, rho)
+    }
+
+    /// `sqrt(-2α) sech(sqrt(2α/β)·θ)`, with the phase locked to the pump
+    fn soliton(&self) -> anyhow::Result<impl Fn(f64) -> Complex64> {
+        let Self { alpha, pump, beta } = *self;
+        ensure!(alpha < 0., "Solitons need a negative detuning α");
+        ensure!(
+            beta < 0.,
+            "Solitons need an anomalous (negative) dispersion β"
+        );
+        let amplitude = (-2. * alpha).sqrt();
+        let k = (2. * alpha / beta).sqrt();
+        let cos_phase = (-8. * alpha).sqrt() / (PI * pump);
+        // conjugated as the field
+        let phase = -if cos_phase.abs() <= 1. {
+            cos_phase.acos()
+        } else {
+            FRAC_PI_2
+        };
+        Ok(move |theta: f64| Complex64::from_polar(amplitude / (k * theta).cosh(), phase))
+    }
+
+    /// Mode number of the largest modulation instability gain around the power `rho`
+    fn turing_mode(&self, rho: f64) -> anyhow::Result<i32> {
+        let Self { alpha, beta, .. } = *self;
+        ensure!(
+            beta < 0.,
+            "Turing rolls need an anomalous (negative) dispersion β"
+        );
+        // the gain peaks at `ρ - 1`, where the dispersion makes up for `2ρ + α`
+        ensure!(
+            rho > 1.,
+            "The CW state is stable against modulation instability, below ρ = 1"
+        );
+        ensure!(
+            2. * rho + alpha > 0.,
+            "The modulation instability gain peaks at the pump mode"
+        );
+        Ok(((2. * (2. * rho + alpha) / -beta).sqrt().round() as i32).max(1))
+    }
+}
+
+/// `θ` wrapped into [-π, π)
+fn wrap(theta: f64) -> f64 {
+    (theta + PI).rem_euclid(TAU) - PI
+}
+
+impl Seed {
+    fn build(&self, lle: Lle, n: usize) -> anyhow::Result<Vec<Complex64>> {
+        let (background, rho) = lle.cw(self.background);
+        let points = (0..n).map(|j| theta(j, n));
+        Ok(match self.kind {
+            SeedKind::Noise => vec![Complex64::default(); n],
+            SeedKind::Cw => vec![background; n],
+            SeedKind::Soliton => {
+                let soliton = lle.soliton()?;
+                points.map(|t| background + soliton(t)).collect()
+            }
+            SeedKind::Solitons => {
+                let soliton = lle.soliton()?;
+                let count = self.solitons.max(1);
+                points
+                    .map(|t| {
+                        background
+                            + (0..count)
+                                .map(|k| {
+                                    let center =
+                                        theta(k as usize, count as usize) + PI / count as f64;
+                                    soliton(wrap(t - center))
+                                })
+                                .sum::<Complex64>()
+                    })
+                    .collect()
+            }
+            SeedKind::Turing => {
+                let mode = lle.turing_mode(rho)? as f64;
+                points
+                    .map(|t| background * (1. + 0.2 * (mode * t).cos()))
+                    .collect()
+            }
+        })
+    }
+
+    /// Replace the state of `core` by the preset, with the noise added
+    pub(crate) fn apply<C, S>(&self, core: &mut Core<C, S>) -> anyhow::Result<()>
+    where
+        C: Controller<S>,
+        S: Simulator,
+    {
+        if self.kind != SeedKind::Noise {
+            let lle = Lle::from_params(&mut core.controller)?;
+            let mut state = core.simulator.get_owned_state();
+            for c in state.components_mut() {
+                let values = self.build(lle, c.len())?;
+                c.copy_from_slice(&values);
+            }
+            core.simulator.set_owned_state(state);
+        }
+        core.add_random();
+        Ok(())
+    }
+
+    pub(crate) fn desc(&self) -> String {
+        match self.kind {
+            SeedKind::Noise => self.kind.desc().to_string(),
+            SeedKind::Solitons => format!("{} solitons", self.solitons),
+            _ => format!(
+                "{} ({} CW branch)",
+                self.kind.desc(),
+                self.background.desc()
+            ),
+        }
+    }
+}
+
+impl ControllerUI for Seed {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("seed preset")
+                .selected_text(self.kind.desc())
+                .show_ui(ui, |ui| self.kind.show_controller(ui));
+            if self.kind == SeedKind::Solitons {
+                ui.add(
+                    egui::DragValue::new(&mut self.solitons)
+                        .range(1..=64)
+                        .prefix("N = "),
+                );
+            }
+            if self.kind != SeedKind::Noise {
+                ui.label("Background")
+                    .on_hover_text("Branch of the homogeneous steady state, which solitons lie on");
+                self.background.show_controller(ui);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lle::{NoneOp, SPhaMod};
+
+    use super::*;
+    use crate::controller::{LleController, LleSolver};
+
+    #[test]
+    fn soliton_of_the_default_model() {
+        let mut core: Core<LleController, LleSolver<SPhaMod, Complex64, NoneOp<f64>>> =
+            Core::new(LleController::default(), 256);
+        let seed = Seed {
+            kind: SeedKind::Soliton,
+            background: Branch::Lower,
+            ..Default::default()
+        };
+        seed.apply(&mut core).unwrap();
+        core.sync_paras();
+        core.simulator.run(500);
+        let power = core
+            .simulator
+            .get_owned_state()
+            .iter()
+            .map(|x| x.norm_sqr())
+            .collect::<Vec<_>>();
+        let peak = power.iter().copied().fold(0., f64::max);
+        let (_, rho) = Lle::from_params(&mut core.controller)
+            .unwrap()
+            .cw(Branch::Lower);
+        // a peak of about `-2α` over the lower CW branch
+        assert!(peak > 5. * rho, "peak {peak}, background {rho}");
+        let wide = power.iter().filter(|&&p| p > peak / 2.).count();
+        assert!(wide < power.len() / 8, "{wide} points over half the peak");
+    }
+}
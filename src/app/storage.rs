@@ -7,7 +7,12 @@ use crate::{
     views::Views,
 };
 
-use super::{Core, ShowDispersion};
+use super::{
+    Core, ShowDispersion, automation::Automation, budget::StepBudget, comb::CombMetrics,
+    compare::Compare, convergence::Convergence, guard::Guard, history::History,
+    init_state::InitEditor, journal::Recorder, links::Links, phase_diagram::PhaseDiagram,
+    runner::Runner, seed::Seed, tabs::TabStorage, triggers::Triggers,
+};
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(bound(
@@ -22,6 +27,10 @@ where
     pub(crate) controller: P,
     pub(crate) simulator_state: S::OwnedState,
     pub(crate) random: RandomNoise,
+    #[serde(default)]
+    pub(crate) automation: Automation,
+    #[serde(default)]
+    pub(crate) links: Links,
 }
 
 impl<P, S: Simulator> Clone for CoreStorage<P, S>
@@ -35,6 +44,8 @@ where
             controller: self.controller.clone(),
             simulator_state: self.simulator_state.clone(),
             random: self.random.clone(),
+            automation: self.automation.clone(),
+            links: self.links.clone(),
         }
     }
 }
@@ -50,6 +61,8 @@ where
             controller: core.controller.clone(),
             simulator_state: core.simulator.get_owned_state(),
             random: core.random.clone(),
+            automation: core.automation.clone(),
+            links: core.links.clone(),
         }
     }
 }
@@ -67,6 +80,8 @@ where
             controller: storage.controller,
             simulator: e,
             random: storage.random,
+            automation: storage.automation,
+            links: storage.links,
         }
     }
 }
@@ -83,6 +98,8 @@ where
             controller: C::default(),
             simulator_state: S::default_state(dim),
             random: RandomNoise::default(),
+            automation: Automation::default(),
+            links: Links::default(),
         }
     }
 }
@@ -107,6 +124,30 @@ where
     pub(crate) views: Views<V>,
     #[serde(skip)]
     pub(crate) running: bool,
+    #[serde(default)]
+    pub(crate) runner: Runner<P, S>,
+    #[serde(default)]
+    pub(crate) budget: StepBudget,
+    #[serde(default)]
+    pub(crate) history: History<P, S>,
+    #[serde(default)]
+    pub(crate) recorder: Recorder<P, S>,
+    #[serde(default)]
+    pub(crate) guard: Guard<P, S>,
+    #[serde(default)]
+    pub(crate) convergence: Convergence,
+    #[serde(default)]
+    pub(crate) compare: Compare<P, S>,
+    #[serde(default)]
+    pub(crate) phase_diagram: PhaseDiagram,
+    #[serde(default)]
+    pub(crate) comb: CombMetrics,
+    #[serde(default)]
+    pub(crate) triggers: Triggers,
+    #[serde(default)]
+    pub(crate) init_editor: InitEditor,
+    #[serde(default)]
+    pub(crate) seed: Seed,
     #[serde(skip)]
     pub(crate) profiler: bool,
     pub(crate) add_rand: bool,
@@ -115,6 +156,11 @@ where
     pub(crate) check_points: checkpoint::CheckPoints<CoreStorage<P, S>>,
     pub(crate) file_state: file::FileManager,
     pub(crate) file_checkpoints: file::FileManager,
+    /// the other tabs of the workspace, the active one is stored above
+    #[serde(default)]
+    pub(crate) tabs: Vec<TabStorage<P, S, V, T>>,
+    #[serde(default)]
+    pub(crate) active_tab: usize,
 }
 
 impl<P, S, V, T> Default for GenAppStorage<P, S, V, T>
@@ -132,12 +178,26 @@ where
             slider_len: None,
             views: Default::default(),
             running: false,
+            runner: Default::default(),
+            budget: Default::default(),
+            history: Default::default(),
+            recorder: Default::default(),
+            guard: Default::default(),
+            convergence: Default::default(),
+            compare: Default::default(),
+            phase_diagram: Default::default(),
+            comb: Default::default(),
+            triggers: Default::default(),
+            init_editor: Default::default(),
+            seed: Default::default(),
             profiler: false,
             add_rand: false,
             show_dispersion: ShowDispersion::default(),
             check_points: Default::default(),
             file_state: FileManager::default_state(),
             file_checkpoints: FileManager::default_check_points(),
+            tabs: Vec::new(),
+            active_tab: 0,
         }
     }
 }
@@ -0,0 +1,453 @@
+use crate::{
+    checkpoint::CheckPoints,
+    controller::{Controller, Simulator},
+    preview::{PreviewTarget, Previewer},
+    views::Views,
+};
+
+use super::{
+    Core, CoreStorage, GenApp, budget::StepBudget, compare::Compare, convergence::Convergence,
+    guard::Guard, history::History, impls::check_batch, init_state::InitEditor, journal::Recorder,
+    runner::Runner, seed::Seed, triggers::Triggers,
+};
+
+/// The simulations of the workspace, one of them shown by the [`GenApp`]
+pub(crate) struct Workspace<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S>,
+{
+    tabs: Vec<Tab<P, S, V, T>>,
+    active: usize,
+}
+
+struct Tab<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S>,
+{
+    name: String,
+    /// keep stepping while another tab is shown
+    keep_running: bool,
+    /// `None` for the active tab, whose simulation lives in the app
+    content: Option<TabContent<P, S, V, T>>,
+}
+
+/// Simulation of a tab, swapped with the fields of the [`GenApp`] when shown
+struct TabContent<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S>,
+{
+    core: Core<P, S>,
+    views: Views<V>,
+    check_points: CheckPoints<CoreStorage<P, S>>,
+    scout: Previewer<P, S, T>,
+    runner: Runner<P, S>,
+    budget: StepBudget,
+    history: History<P, S>,
+    recorder: Recorder<P, S>,
+    guard: Guard<P, S>,
+    convergence: Convergence,
+    compare: Compare<P, S>,
+    triggers: Triggers,
+    init_editor: InitEditor,
+    seed: Seed,
+    running: bool,
+    /// noise added before every batch, per tab
+    add_rand: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "CoreStorage<P, S>: serde::Serialize, Views<V>: serde::Serialize, T: serde::Serialize",
+    deserialize = "CoreStorage<P, S>: for<'a> serde::Deserialize<'a>, Views<V>: for<'a> serde::Deserialize<'a> + Default, T: for<'a> serde::Deserialize<'a>"
+))]
+pub(crate) struct TabStorage<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S> + Default,
+{
+    name: String,
+    keep_running: bool,
+    content: Option<TabContentStorage<P, S, V, T>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "CoreStorage<P, S>: serde::Serialize, Views<V>: serde::Serialize, T: serde::Serialize",
+    deserialize = "CoreStorage<P, S>: for<'a> serde::Deserialize<'a>, Views<V>: for<'a> serde::Deserialize<'a> + Default, T: for<'a> serde::Deserialize<'a>"
+))]
+struct TabContentStorage<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S> + Default,
+{
+    core: CoreStorage<P, S>,
+    #[serde(default)]
+    views: Views<V>,
+    check_points: CheckPoints<CoreStorage<P, S>>,
+    #[serde(default)]
+    scout: Previewer<P, S, T>,
+    #[serde(default)]
+    runner: Runner<P, S>,
+    #[serde(default)]
+    budget: StepBudget,
+    #[serde(default)]
+    history: History<P, S>,
+    #[serde(default)]
+    recorder: Recorder<P, S>,
+    #[serde(default)]
+    guard: Guard<P, S>,
+    #[serde(default)]
+    convergence: Convergence,
+    #[serde(default)]
+    compare: Compare<P, S>,
+    #[serde(default)]
+    triggers: Triggers,
+    #[serde(default)]
+    init_editor: InitEditor,
+    #[serde(default)]
+    seed: Seed,
+    #[serde(default)]
+    add_rand: bool,
+}
+
+enum TabAction {
+    Switch(usize),
+    Duplicate,
+    Close(usize),
+}
+
+fn tab_name(i: usize) -> String {
+    format!("Simulation {}", i + 1)
+}
+
+impl<P, S, V, T> Default for Workspace<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S>,
+{
+    fn default() -> Self {
+        Self {
+            tabs: vec![Tab {
+                name: tab_name(0),
+                keep_running: false,
+                content: None,
+            }],
+            active: 0,
+        }
+    }
+}
+
+impl<P, S, V, T> Workspace<P, S, V, T>
+where
+    P: Controller<S>,
+    S: Simulator,
+    T: PreviewTarget<P, S> + Default,
+{
+    pub(crate) fn from_storage(tabs: Vec<TabStorage<P, S, V, T>>, active: usize) -> Self {
+        // exactly the active tab has its simulation in the app
+        let valid = tabs
+            .iter()
+            .enumerate()
+            .all(|(i, t)| t.content.is_none() == (i == active));
+        if active >= tabs.len() || !valid {
+            return Self::default();
+        }
+        let tabs = tabs
+            .into_iter()
+            .map(|t| Tab {
+                name: t.name,
+                keep_running: t.keep_running,
+                content: t.content.map(|c| TabContent {
+                    core: c.core.into(),
+                    views: c.views,
+                    check_points: c.check_points,
+                    scout: c.scout,
+                    runner: c.runner,
+                    budget: c.budget,
+                    history: c.history,
+                    recorder: c.recorder,
+                    guard: c.guard,
+                    convergence: c.convergence,
+                    compare: c.compare,
+                    triggers: c.triggers,
+                    init_editor: c.init_editor,
+                    seed: c.seed,
+                    running: false,
+                    add_rand: c.add_rand,
+                }),
+            })
+            .collect();
+        Self { tabs, active }
+    }
+}
+
+impl<P, S, V, T> Workspace<P, S, V, T>
+where
+    P: Clone + Controller<S> + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+    T: PreviewTarget<P, S> + Default + Clone,
+    Views<V>: Clone,
+{
+    pub(crate) fn to_storage(&self) -> (Vec<TabStorage<P, S, V, T>>, usize) {
+        let tabs = self
+            .tabs
+            .iter()
+            .map(|t| TabStorage {
+                name: t.name.clone(),
+                keep_running: t.keep_running,
+                content: t.content.as_ref().map(|c| TabContentStorage {
+                    core: (&c.core).into(),
+                    views: c.views.clone(),
+                    check_points: c.check_points.clone(),
+                    scout: c.scout.clone_for_save(),
+                    runner: c.runner.clone_for_save(),
+                    budget: c.budget.clone(),
+                    history: c.history.clone_for_save(),
+                    recorder: c.recorder.clone_for_save(),
+                    guard: c.guard.clone_for_save(),
+                    convergence: c.convergence.clone_for_save(),
+                    compare: c.compare.clone_for_save(),
+                    triggers: c.triggers.clone(),
+                    init_editor: c.init_editor.clone(),
+                    seed: c.seed.clone(),
+                    add_rand: c.add_rand,
+                }),
+            })
+            .collect();
+        (tabs, self.active)
+    }
+
+    fn show(&mut self, ctx: &egui::Context) -> Option<TabAction> {
+        let mut action = None;
+        egui::TopBottomPanel::top("workspace tabs").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                let closable = self.tabs.len() > 1;
+                for (i, tab) in self.tabs.iter_mut().enumerate() {
+                    let mut text = egui::RichText::new(&tab.name);
+                    if tab.keep_running {
+                        text = text.italics();
+                    }
+                    let r = ui.selectable_label(i == self.active, text).on_hover_text(
+                        "Right click to rename or to keep it running in the background",
+                    );
+                    if r.clicked() && i != self.active {
+                        action = Some(TabAction::Switch(i));
+                    }
+                    r.context_menu(|ui| {
+                        ui.text_edit_singleline(&mut tab.name);
+                        ui.checkbox(&mut tab.keep_running, "Keep running in the background");
+                        if closable && ui.button("🗙 Close").clicked() {
+                            action = Some(TabAction::Close(i));
+                            ui.close();
+                        }
+                    });
+                }
+                if ui
+                    .button("➕")
+                    .on_hover_text("Duplicate the current simulation into a new tab")
+                    .clicked()
+                {
+                    action = Some(TabAction::Duplicate);
+                }
+            });
+        });
+        action
+    }
+}
+
+impl<P, S, V, T, D> GenApp<P, S, V, T, D>
+where
+    P: Default + Clone + Controller<S> + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+    T: PreviewTarget<P, S> + Default + Clone,
+    Views<V>: Clone,
+{
+    /// Exchange the simulation shown by the app with `content`
+    fn swap_tab(&mut self, content: &mut TabContent<P, S, V, T>) {
+        std::mem::swap(&mut self.core, &mut content.core);
+        std::mem::swap(&mut self.views, &mut content.views);
+        std::mem::swap(&mut self.check_points, &mut content.check_points);
+        std::mem::swap(&mut self.scout, &mut content.scout);
+        std::mem::swap(&mut self.runner, &mut content.runner);
+        std::mem::swap(&mut self.budget, &mut content.budget);
+        std::mem::swap(&mut self.history, &mut content.history);
+        std::mem::swap(&mut self.recorder, &mut content.recorder);
+        std::mem::swap(&mut self.guard, &mut content.guard);
+        std::mem::swap(&mut self.convergence, &mut content.convergence);
+        std::mem::swap(&mut self.compare, &mut content.compare);
+        std::mem::swap(&mut self.triggers, &mut content.triggers);
+        std::mem::swap(&mut self.init_editor, &mut content.init_editor);
+        std::mem::swap(&mut self.seed, &mut content.seed);
+        std::mem::swap(&mut self.running, &mut content.running);
+        std::mem::swap(&mut self.add_rand, &mut content.add_rand);
+    }
+
+    fn switch_tab(&mut self, i: usize) {
+        let active = self.workspace.active;
+        let Some(mut content) = self.workspace.tabs[i].content.take() else {
+            return;
+        };
+        self.swap_tab(&mut content);
+        self.workspace.tabs[active].content = Some(content);
+        self.workspace.active = i;
+    }
+
+    pub(crate) fn show_tabs(&mut self, ctx: &egui::Context) {
+        match self.workspace.show(ctx) {
+            Some(TabAction::Switch(i)) => self.switch_tab(i),
+            Some(TabAction::Duplicate) => {
+                let content = TabContent {
+                    core: CoreStorage::from(&self.core).into(),
+                    views: self.views.clone(),
+                    check_points: self.check_points.clone(),
+                    scout: self.scout.clone_for_save(),
+                    runner: self.runner.clone_for_save(),
+                    budget: self.budget.clone(),
+                    history: Default::default(),
+                    recorder: Default::default(),
+                    guard: self.guard.clone_for_save(),
+                    convergence: self.convergence.clone_for_save(),
+                    compare: self.compare.clone_for_save(),
+                    triggers: self.triggers.clone(),
+                    init_editor: self.init_editor.clone(),
+                    seed: self.seed.clone(),
+                    running: false,
+                    add_rand: self.add_rand,
+                };
+                let tabs = &mut self.workspace.tabs;
+                let i = tabs.len();
+                tabs.push(Tab {
+                    name: tab_name(i),
+                    keep_running: false,
+                    content: Some(content),
+                });
+                self.switch_tab(i);
+            }
+            Some(TabAction::Close(i)) => {
+                if i == self.workspace.active {
+                    self.switch_tab(if i == 0 { 1 } else { i - 1 });
+                }
+                self.workspace.tabs.remove(i);
+                if self.workspace.active > i {
+                    self.workspace.active -= 1;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Step the hidden tabs kept running, without their previews.
+    /// Their batches are checked as the ones of the active tab, see [`check_batch`]
+    pub(crate) fn run_background_tabs(&mut self) -> bool {
+        let mut busy = false;
+        for c in self
+            .workspace
+            .tabs
+            .iter_mut()
+            .filter(|t| t.keep_running)
+            .filter_map(|t| t.content.as_mut())
+        {
+            let TabContent {
+                core,
+                check_points,
+                runner,
+                budget,
+                guard,
+                recorder,
+                convergence,
+                compare,
+                triggers,
+                running,
+                add_rand,
+                ..
+            } = c;
+            let mut batch = runner.poll(core);
+            if let Some(b) = &batch {
+                recorder.batch_done(b);
+            }
+            if let Some(b) = &batch
+                && check_batch(
+                    core,
+                    runner,
+                    guard,
+                    convergence,
+                    recorder,
+                    triggers,
+                    check_points,
+                    b,
+                )
+            {
+                *running = false;
+            }
+            if recorder.is_replaying() {
+                // as in the active tab, the journal sets the parameters and the noise
+                if *running
+                    && runner.is_ready()
+                    && let Some((steps, noise, step_dist)) = recorder.next_batch(core, runner)
+                {
+                    core.controller.sync_paras(&mut core.simulator);
+                    let stepped = runner.step(core, steps, noise, step_dist);
+                    if let Some(b) = &stepped
+                        && check_batch(
+                            core,
+                            runner,
+                            guard,
+                            convergence,
+                            recorder,
+                            triggers,
+                            check_points,
+                            b,
+                        )
+                    {
+                        *running = false;
+                    }
+                    batch = batch.or(stepped);
+                }
+            } else if *running {
+                let cur_step = runner.cur_step(core);
+                core.sync_paras_at(cur_step);
+                let mut steps = budget.steps(core.controller.steps());
+                if let Some(left) = triggers.steps_until(cur_step) {
+                    steps = steps.min(left);
+                }
+                if runner.is_ready() {
+                    recorder.batch(cur_step, core, steps, *add_rand);
+                    compare.step(steps, *add_rand, runner.background);
+                }
+                let stepped = runner.step(core, steps, *add_rand, None);
+                if let Some(b) = &stepped {
+                    recorder.batch_done(b);
+                }
+                if let Some(b) = &stepped
+                    && check_batch(
+                        core,
+                        runner,
+                        guard,
+                        convergence,
+                        recorder,
+                        triggers,
+                        check_points,
+                        b,
+                    )
+                {
+                    *running = false;
+                }
+                batch = batch.or(stepped);
+            }
+            if let Some(batch) = batch {
+                budget.record(batch.steps, batch.elapsed);
+            }
+            busy |= *running || runner.is_busy();
+        }
+        busy
+    }
+}
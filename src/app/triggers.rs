@@ -0,0 +1,355 @@
+use ui_traits::{ControllerUI, DisplayStr};
+
+use crate::{
+    checkpoint::{CheckPoint, CheckPoints},
+    controller::{Components, Controller, Simulator, StoreState},
+};
+
+use super::{
+    Core, CoreStorage,
+    convergence::{Convergence, Regime},
+};
+
+/// Conditions checked after every batch, pausing, checkpointing or notifying when they fire
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Triggers {
+    triggers: Vec<Trigger>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Trigger {
+    /// disarmed after firing
+    armed: bool,
+    condition: Condition,
+    pause: bool,
+    checkpoint: bool,
+    toast: bool,
+    /// measure after the last batch, with its step
+    #[serde(skip)]
+    last: Option<(u32, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+enum Condition {
+    /// relative change of the energy per step below `epsilon`
+    Steady {
+        epsilon: f64,
+    },
+    /// number of peaks of `|ψ|²` above `threshold` changes
+    PeakCount {
+        threshold: f64,
+    },
+    /// maximum of `|ψ|²` crosses `threshold`, either way
+    PeakPower {
+        threshold: f64,
+    },
+    Step {
+        step: u32,
+    },
+    /// the [`Convergence`] indicator reads stationary, or drifting if allowed
+    Converged {
+        drifting: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_iterator::Sequence)]
+enum ConditionKind {
+    Steady,
+    PeakCount,
+    PeakPower,
+    Step,
+    Converged,
+}
+
+impl DisplayStr for ConditionKind {
+    fn desc(&self) -> &str {
+        match self {
+            ConditionKind::Steady => "Steady energy",
+            ConditionKind::PeakCount => "Soliton count changes",
+            ConditionKind::PeakPower => "Peak power crosses",
+            ConditionKind::Step => "Step reached",
+            ConditionKind::Converged => "Converged",
+        }
+    }
+}
+
+/// Local maxima of `|ψ|²` above `threshold`, on the periodic domain
+pub(crate) fn count_peaks(field: &[lle::num_complex::Complex64], threshold: f64) -> usize {
+    let n = field.len();
+    (0..n)
+        .filter(|&j| {
+            let p = field[j].norm_sqr();
+            p > threshold
+                && p > field[(j + n - 1) % n].norm_sqr()
+                && p >= field[(j + 1) % n].norm_sqr()
+        })
+        .count()
+}
+
+impl Condition {
+    fn new(kind: ConditionKind) -> Self {
+        match kind {
+            ConditionKind::Steady => Condition::Steady { epsilon: 1E-9 },
+            ConditionKind::PeakCount => Condition::PeakCount { threshold: 1. },
+            ConditionKind::PeakPower => Condition::PeakPower { threshold: 1. },
+            ConditionKind::Step => Condition::Step { step: 1_000_000 },
+            ConditionKind::Converged => Condition::Converged { drifting: true },
+        }
+    }
+
+    fn kind(&self) -> ConditionKind {
+        match self {
+            Condition::Steady { .. } => ConditionKind::Steady,
+            Condition::PeakCount { .. } => ConditionKind::PeakCount,
+            Condition::PeakPower { .. } => ConditionKind::PeakPower,
+            Condition::Step { .. } => ConditionKind::Step,
+            Condition::Converged { .. } => ConditionKind::Converged,
+        }
+    }
+
+    fn measure(
+        &self,
+        fields: &[&mut [lle::num_complex::Complex64]],
+        regime: Option<Regime>,
+        step: u32,
+    ) -> f64 {
+        let powers = || fields.iter().flat_map(|f| f.iter().map(|x| x.norm_sqr()));
+        match *self {
+            Condition::Steady { .. } => powers().sum(),
+            Condition::PeakCount { threshold } => fields
+                .iter()
+                .map(|f| count_peaks(f, threshold))
+                .sum::<usize>() as f64,
+            Condition::PeakPower { .. } => powers().fold(0., f64::max),
+            Condition::Step { .. } => step as f64,
+            Condition::Converged { drifting } => match regime {
+                Some(Regime::Stationary) => 1.,
+                Some(Regime::Drifting) if drifting => 1.,
+                _ => 0.,
+            },
+        }
+    }
+
+    /// Whether it fires from the measure `cur` alone, without the previous one
+    fn is_stateless(&self) -> bool {
+        matches!(self, Condition::Step { .. } | Condition::Converged { .. })
+    }
+
+    /// Whether it fires between the measures `prev` and `cur`, `steps` steps apart,
+    /// `prev` being NaN for a stateless condition without one
+    fn fires(&self, prev: f64, cur: f64, steps: u32) -> bool {
+        match *self {
+            Condition::Steady { epsilon } => {
+                prev > 0. && ((cur - prev) / prev).abs() / steps as f64 <= epsilon
+            }
+            Condition::PeakCount { .. } => cur != prev,
+            Condition::PeakPower { threshold } => (prev < threshold) != (cur < threshold),
+            Condition::Step { step } => cur >= step as f64 && cur - (steps as f64) < step as f64,
+            Condition::Converged { .. } => cur > 0.,
+        }
+    }
+
+    fn describe(&self, cur: f64) -> String {
+        match *self {
+            Condition::Steady { .. } => format!("Steady energy {cur:.4E}"),
+            Condition::PeakCount { .. } => format!("Soliton count changed to {cur}"),
+            Condition::PeakPower { threshold } => {
+                format!("Peak power {cur:.4} crossed {threshold}")
+            }
+            Condition::Step { step } => format!("Step {step} reached"),
+            Condition::Converged { .. } => "Converged".to_string(),
+        }
+    }
+}
+
+impl ControllerUI for Condition {
+    fn show_controller(&mut self, ui: &mut egui::Ui) {
+        match self {
+            Condition::Steady { epsilon } => {
+                ui.add(
+                    egui::DragValue::new(epsilon)
+                        .speed(1E-10)
+                        .range(0. ..=f64::INFINITY)
+                        .prefix("ε ")
+                        .custom_formatter(|x, _| format!("{x:E}")),
+                )
+                .on_hover_text("Relative change of the energy per step");
+            }
+            Condition::PeakCount { threshold } | Condition::PeakPower { threshold } => {
+                ui.add(
+                    egui::DragValue::new(threshold)
+                        .speed(1E-2)
+                        .range(0. ..=f64::INFINITY)
+                        .prefix("|ψ|² "),
+                );
+            }
+            Condition::Step { step } => {
+                ui.add(egui::DragValue::new(step).prefix("step "));
+            }
+            Condition::Converged { drifting } => {
+                ui.checkbox(drifting, "or drifting")
+                    .on_hover_text("Also fire when stationary in a moving frame");
+            }
+        }
+    }
+}
+
+/// What fired triggers ask for
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Fired {
+    pub(crate) pause: bool,
+}
+
+impl Triggers {
+    /// Steps left to the closest armed step condition, so a batch stops right at it
+    pub(crate) fn steps_until(&self, step: u32) -> Option<u32> {
+        self.triggers
+            .iter()
+            .filter(|t| t.armed)
+            .filter_map(|t| match t.condition {
+                Condition::Step { step: s } if s > step => Some(s - step),
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Check the triggers after a batch of `steps` ending at `step`,
+    /// with the regime read by the [`Convergence`] indicator
+    pub(crate) fn check<P, S>(
+        &mut self,
+        core: &Core<P, S>,
+        check_points: &mut CheckPoints<CoreStorage<P, S>>,
+        regime: Option<Regime>,
+        step: u32,
+        steps: u32,
+    ) -> Fired
+    where
+        P: Controller<S> + Clone,
+        S: Simulator,
+    {
+        let mut fired = Fired::default();
+        if !self.triggers.iter().any(|t| t.armed) {
+            return fired;
+        }
+        let mut state = core.simulator.get_owned_state();
+        let fields = state.components_mut();
+        for t in self.triggers.iter_mut().filter(|t| t.armed) {
+            let cur = t.condition.measure(&fields, regime, step);
+            // the state may have been replaced since the last measure
+            let prev = t
+                .last
+                .replace((step, cur))
+                .filter(|(s, _)| s.checked_add(steps) == Some(step));
+            let prev = match prev {
+                Some((_, prev)) => prev,
+                None if t.condition.is_stateless() => f64::NAN,
+                None => continue,
+            };
+            if !t.condition.fires(prev, cur, steps) {
+                continue;
+            }
+            t.armed = false;
+            let desc = t.condition.describe(cur);
+            fired.pause |= t.pause;
+            if t.checkpoint {
+                check_points.push(CheckPoint {
+                    name: Some(format!("{desc} at step {step}")),
+                    state: core.into(),
+                });
+            }
+            if t.toast {
+                crate::notify::TOASTS
+                    .lock()
+                    .info(format!("{desc} at step {step}"));
+            }
+        }
+        fired
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Stop conditions", |ui| {
+            let mut to_remove = None;
+            for (i, t) in self.triggers.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .checkbox(&mut t.armed, "")
+                            .on_hover_text("Armed, disarmed after firing")
+                            .changed()
+                        {
+                            t.last = None;
+                        }
+                        let mut kind = t.condition.kind();
+                        egui::ComboBox::from_id_salt("condition")
+                            .selected_text(kind.desc())
+                            .show_ui(ui, |ui| kind.show_controller(ui));
+                        if kind != t.condition.kind() {
+                            t.condition = Condition::new(kind);
+                            t.last = None;
+                        }
+                        if ui.button("🗑").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let before = t.condition;
+                        t.condition.show_controller(ui);
+                        if before != t.condition {
+                            t.last = None;
+                        }
+                        ui.toggle_value(&mut t.pause, "⏸")
+                            .on_hover_text("Pause the simulation");
+                        ui.toggle_value(&mut t.checkpoint, "📍")
+                            .on_hover_text("Add a checkpoint");
+                        ui.toggle_value(&mut t.toast, "🔔").on_hover_text("Notify");
+                    });
+                });
+                ui.separator();
+            }
+            if let Some(i) = to_remove {
+                self.triggers.remove(i);
+            }
+            if ui.button("➕").on_hover_text("Add a condition").clicked() {
+                self.triggers.push(Trigger {
+                    armed: true,
+                    condition: Condition::new(ConditionKind::Steady),
+                    pause: true,
+                    checkpoint: true,
+                    toast: true,
+                    last: None,
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lle::num_complex::Complex64;
+
+    use super::*;
+
+    #[test]
+    fn conditions() {
+        let field = [0., 2., 0.5, 0., 3., 0., 0.5, 2.5]
+            .map(|x: f64| Complex64::new(x.sqrt(), 0.))
+            .to_vec();
+        assert_eq!(count_peaks(&field, 1.), 3);
+        assert_eq!(count_peaks(&field, 2.2), 2);
+
+        let steady = Condition::Steady { epsilon: 1E-6 };
+        assert!(steady.fires(1., 1. + 1E-5, 100));
+        assert!(!steady.fires(1., 1. + 1E-3, 100));
+        let peak = Condition::PeakPower { threshold: 2. };
+        assert!(peak.fires(1., 3., 10));
+        assert!(peak.fires(3., 1., 10));
+        assert!(!peak.fires(3., 2.5, 10));
+        let step = Condition::Step { step: 100 };
+        assert!(step.fires(50., 150., 100));
+        assert!(!step.fires(150., 250., 100));
+        // reached in the first batch after arming, with no previous measure
+        assert!(step.is_stateless());
+        assert!(step.fires(f64::NAN, 100., 100));
+        assert!(!step.fires(f64::NAN, 99., 100));
+    }
+}
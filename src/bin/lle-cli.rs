@@ -0,0 +1,13 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    env_logger::builder()
+        .filter_module("lle_gui", log::LevelFilter::Info)
+        .init();
+    lle_gui::cli::run(std::env::args().skip(1))
+}
+
+// The runner is native only
+#[cfg(target_arch = "wasm32")]
+fn main() {}
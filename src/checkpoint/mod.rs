@@ -27,6 +27,10 @@ impl<S> CheckPoints<S> {
         self.checkpoints.push(t.checkpoint());
     }
 
+    pub fn push(&mut self, checkpoint: CheckPoint<S>) {
+        self.checkpoints.push(checkpoint);
+    }
+
     pub fn restore<T: Restorable<Store = S>>(&mut self, t: &mut T, index: usize) {
         t.restore_by_ref(&self.checkpoints[index]);
     }
@@ -127,3 +131,45 @@ impl<S> CheckPoints<S> {
         changed
     }
 }
+
+#[cfg(test)]
+mod test {
+    use lle::{NoneOp, SPhaMod, num_complex::Complex64};
+
+    use super::*;
+    use crate::{
+        controller::{LleController, LleSolver, StoreState},
+        random::RandomNoise,
+    };
+
+    type LleCore = Core<LleController, LleSolver<SPhaMod, Complex64, NoneOp<f64>>>;
+
+    fn run(core: &mut LleCore, batches: usize) {
+        for _ in 0..batches {
+            core.add_random();
+            core.simulator.run(10);
+        }
+    }
+
+    #[test]
+    fn restore_continues_the_trajectory() {
+        let mut core = LleCore::new(LleController::default(), 128);
+        core.random = RandomNoise::new(1E-3, Some(42));
+        run(&mut core, 5);
+
+        let mut check_points = CheckPoints::default();
+        check_points.add(&mut core);
+        // through the file format
+        let mut check_points: CheckPoints<CoreStorage<_, _>> =
+            ron::from_str(&ron::to_string(&check_points).unwrap()).unwrap();
+        run(&mut core, 5);
+
+        let mut restored = LleCore::default();
+        check_points.restore(&mut restored, 0);
+        run(&mut restored, 5);
+        assert_eq!(
+            core.simulator.get_owned_state(),
+            restored.simulator.get_owned_state()
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! Headless runner for models saved from the GUI
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use ui_traits::Params;
+
+use crate::{
+    FftSource,
+    app::{AppTypes, Core, CoreStorage},
+    checkpoint::{CheckPoint, CheckPoints},
+    construct::{Model, with_model},
+    controller::{Controller, Fields, Simulator},
+    drawer::Process,
+    file::Extension,
+};
+
+const USAGE: &str = "\
+Usage: lle-cli <MODEL.ron> --steps <N> [OPTIONS]
+
+Run a model saved by the GUI (\"Save/Load model\") without a window.
+
+Options:
+    --steps <N>             number of solver steps to run
+    --model <ID>            model of the file, guessed from `name.<ext>.ron` if omitted,
+                            required for `lle` files, saved by both lle and fp
+                            (lle, fp, clle, cprt, cprt2, dis, dis2, slle, islle, plle, dplle, gencprt)
+    --out <DIR>             output directory [default: lle-cli-out]
+    --checkpoint-every <N>  add a checkpoint every N steps
+    --trace-every <N>       record the real and frequency domain traces every N steps
+                            [default: only the final state]
+    --noise                 add the saved random noise before every batch of steps,
+                            one every `steps` of the model
+    --start-step <N>        step the model was saved at, the schedules going on from it
+                            and the records counting from it [default: 0]
+    --set <PATH>=<VALUE>    set the parameter at PATH, like `disper.couple_strength=0.2`,
+                            before running; repeatable
+    -h, --help              print this message
+
+Outputs, in the output directory:
+    final.<ext>.ron         final state, loadable by the GUI
+    checkpoints.<ext>.cp.ron
+                            checkpoints, loadable by the GUI
+    real[_i].csv            |field| of each field, one `step,values...` row per record
+    freq[_i].csv            spectrum of each field in dB, one `step,values...` row per record
+";
+
+#[derive(Debug)]
+struct Args {
+    input: PathBuf,
+    model: Model,
+    steps: u64,
+    out: PathBuf,
+    checkpoint_every: Option<u64>,
+    trace_every: Option<u64>,
+    noise: bool,
+    /// step the input was saved at, the automation is evaluated from it
+    start_step: u64,
+    /// parameters to set before running, by path
+    set: Vec<(String, f64)>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<Self>> {
+        fn interval(v: Option<String>, name: &str) -> anyhow::Result<u64> {
+            let v: u64 = v
+                .with_context(|| format!("missing value of {name}"))?
+                .parse()
+                .with_context(|| format!("invalid value of {name}"))?;
+            if v == 0 {
+                bail!("{name} must be positive");
+            }
+            Ok(v)
+        }
+
+        let mut input = None;
+        let mut model = None;
+        let mut steps = None;
+        let mut out = PathBuf::from("lle-cli-out");
+        let mut checkpoint_every = None;
+        let mut trace_every = None;
+        let mut noise = false;
+        let mut start_step = 0;
+        let mut set = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--steps" => steps = Some(interval(args.next(), "--steps")?),
+                "--model" => {
+                    let id = args.next().context("missing value of --model")?;
+                    model =
+                        Some(Model::from_id(&id).with_context(|| format!("unknown model {id}"))?);
+                }
+                "--out" => out = args.next().context("missing value of --out")?.into(),
+                "--checkpoint-every" => {
+                    checkpoint_every = Some(interval(args.next(), "--checkpoint-every")?)
+                }
+                "--trace-every" => trace_every = Some(interval(args.next(), "--trace-every")?),
+                "--noise" => noise = true,
+                "--start-step" => {
+                    start_step = args
+                        .next()
+                        .context("missing value of --start-step")?
+                        .parse()
+                        .context("invalid value of --start-step")?
+                }
+                "--set" => {
+                    let v = args.next().context("missing value of --set")?;
+                    let (path, value) = v
+                        .split_once('=')
+                        .with_context(|| format!("expected PATH=VALUE, got {v}"))?;
+                    let value = value
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("invalid value of {path}"))?;
+                    set.push((path.trim().to_string(), value));
+                }
+                _ if arg.starts_with('-') => bail!("unknown option {arg}"),
+                _ if input.is_none() => input = Some(PathBuf::from(arg)),
+                _ => bail!("unexpected argument {arg}"),
+            }
+        }
+
+        let input = input.context("missing model file")?;
+        let model = match model {
+            Some(m) => m,
+            None => guess_model(&input)?,
+        };
+        Ok(Some(Self {
+            input,
+            model,
+            steps: steps.context("missing --steps")?,
+            out,
+            checkpoint_every,
+            trace_every,
+            noise,
+            start_step,
+            set,
+        }))
+    }
+}
+
+/// Extension of the files the save dialog writes for `model`, shared by the models
+/// of the same controller
+fn extension(model: Model) -> &'static str {
+    with_model!(model, A => <
+        <A as AppTypes>::Controller as Controller<<A as AppTypes>::Simulator>
+    >::EXTENSION)
+}
+
+/// `name.<ext>.ron`, as written by the save dialog
+fn guess_model(path: &Path) -> anyhow::Result<Model> {
+    let ext = path
+        .file_name()
+        .and_then(|n| n.to_str()?.strip_suffix(".ron")?.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .with_context(|| format!("can't tell the model of {}, use --model", path.display()))?;
+    let models = enum_iterator::all::<Model>()
+        .filter(|m| extension(*m) == ext)
+        .collect::<Vec<_>>();
+    match models[..] {
+        [m] => Ok(m),
+        [] => bail!("no model saves `.{ext}.ron` files, use --model"),
+        _ => bail!(
+            "`.{ext}.ron` files are saved by the models {}, pick one with --model",
+            models.iter().map(Model::id).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Entry of the `lle-cli` binary
+pub fn run(args: impl IntoIterator<Item = String>) -> anyhow::Result<()> {
+    let Some(args) = Args::parse(args)? else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+    with_model!(args.model, A => run_model::<
+        <A as AppTypes>::Controller,
+        <A as AppTypes>::Simulator,
+    >(&args))
+}
+
+fn run_model<P, S>(args: &Args) -> anyhow::Result<()>
+where
+    P: Controller<S> + Clone + serde::Serialize + for<'a> serde::Deserialize<'a>,
+    S: Simulator,
+    S::OwnedState: Fields,
+{
+    let data = std::fs::read(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let storage: CoreStorage<P, S> = ron::de::from_bytes(&data)
+        .with_context(|| format!("failed to parse {}", args.input.display()))?;
+    let mut core = Core::from(storage);
+    for (path, value) in &args.set {
+        if !core.controller.set_param(path, *value) {
+            bail!(
+                "no parameter {path} in {}, expected one of: {}",
+                args.model.id(),
+                core.controller.param_paths().join(", ")
+            );
+        }
+    }
+
+    std::fs::create_dir_all(&args.out)
+        .with_context(|| format!("failed to create {}", args.out.display()))?;
+    let final_path = args
+        .out
+        .join(format!("final.{}.ron", CoreStorage::<P, S>::extension()));
+    let checkpoints_path = args.out.join(format!(
+        "checkpoints.{}.ron",
+        CheckPoints::<CoreStorage<P, S>>::extension()
+    ));
+
+    let mut traces = Traces::new(&args.out, core.simulator.get_owned_state().fields().len())?;
+    let mut checkpoints = CheckPoints::default();
+
+    log::info!(
+        "running {} for {} steps from {}",
+        args.model.id(),
+        args.steps,
+        args.input.display()
+    );
+    let batch = core.controller.steps().max(1) as u64;
+    let mut done = 0;
+    while done < args.steps {
+        // stop at every batch, checkpoint and trace record
+        let n = [Some(batch), args.checkpoint_every, args.trace_every]
+            .into_iter()
+            .flatten()
+            .map(|every| every - done % every)
+            .fold(args.steps - done, u64::min);
+        let step = args.start_step + done;
+
+        // the solver of the loaded core starts over from 0
+        core.sync_paras_at(u32::try_from(step).unwrap_or(u32::MAX));
+        if args.noise && done % batch == 0 {
+            core.add_random();
+        }
+        core.simulator.run(n as u32);
+        done += n;
+        let step = args.start_step + done;
+
+        if args.checkpoint_every.is_some_and(|e| done % e == 0) {
+            checkpoints.push(CheckPoint {
+                name: Some(format!("step {step}")),
+                state: CoreStorage::from(&core),
+            });
+            write_ron(&checkpoints_path, &checkpoints)?;
+            log::info!("checkpoint at step {step}/{}", args.start_step + args.steps);
+        }
+        if args.trace_every.is_some_and(|e| done % e == 0) {
+            traces.record(step, &core.simulator.get_owned_state().fields())?;
+        }
+    }
+
+    if args.trace_every.is_none_or(|e| done % e != 0) {
+        traces.record(
+            args.start_step + done,
+            &core.simulator.get_owned_state().fields(),
+        )?;
+    }
+    traces.flush()?;
+    write_ron(&final_path, &CoreStorage::from(&core))?;
+    log::info!("final state written to {}", final_path.display());
+    Ok(())
+}
+
+fn write_ron<T: serde::Serialize>(path: &Path, t: &T) -> anyhow::Result<()> {
+    let data = ron::ser::to_string_pretty(t, ron::ser::PrettyConfig::default())?;
+    std::fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Real and frequency domain traces of every field, as csv rows
+struct Traces<F: FftSource> {
+    writers: Vec<[(Process<F>, BufWriter<File>); 2]>,
+}
+
+impl<F: FftSource> Traces<F> {
+    fn new(dir: &Path, fields: usize) -> anyhow::Result<Self> {
+        let create = |name: String| -> anyhow::Result<BufWriter<File>> {
+            let path = dir.join(name);
+            let file = File::create(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            Ok(BufWriter::new(file))
+        };
+        let writers = (0..fields)
+            .map(|i| -> anyhow::Result<_> {
+                let suffix = if fields == 1 {
+                    String::new()
+                } else {
+                    format!("_{i}")
+                };
+                Ok([
+                    (
+                        Process::new_real_domain(),
+                        create(format!("real{suffix}.csv"))?,
+                    ),
+                    (
+                        Process::new_freq_domain(),
+                        create(format!("freq{suffix}.csv"))?,
+                    ),
+                ])
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { writers })
+    }
+
+    fn record(&mut self, step: u64, fields: &[F]) -> anyhow::Result<()> {
+        for (field, writers) in fields.iter().zip(self.writers.iter_mut()) {
+            for (proc, file) in writers {
+                write!(file, "{step}")?;
+                for v in proc.proc(field, false) {
+                    write!(file, ",{v}")?;
+                }
+                writeln!(file)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        for (_, file) in self.writers.iter_mut().flatten() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let args = Args::parse(
+            [
+                "state.gencprt.ron",
+                "--steps",
+                "1000",
+                "--trace-every",
+                "100",
+            ]
+            .map(String::from),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.model, Model::GenCprt);
+        assert_eq!(args.steps, 1000);
+        assert_eq!(args.trace_every, Some(100));
+        assert_eq!(args.checkpoint_every, None);
+        assert_eq!(args.start_step, 0);
+        assert!(args.set.is_empty());
+
+        let args = Args::parse(
+            [
+                "state.lle.ron",
+                "--model",
+                "fp",
+                "--steps",
+                "10",
+                "--set",
+                "alpha=-3.5",
+                "--set",
+                "disper.period = 2",
+                "--start-step",
+                "500",
+            ]
+            .map(String::from),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.model, Model::Fp);
+        assert_eq!(args.start_step, 500);
+        assert_eq!(
+            args.set,
+            vec![
+                ("alpha".to_string(), -3.5),
+                ("disper.period".to_string(), 2.)
+            ]
+        );
+        assert!(
+            Args::parse(["state.lle.ron", "--steps", "10", "--set", "alpha"].map(String::from))
+                .is_err()
+        );
+
+        assert!(Args::parse(["state.ron", "--steps", "10"].map(String::from)).is_err());
+        // saved by both the LLE and the Fabry-Perot models
+        assert!(Args::parse(["state.lle.ron", "--steps", "10"].map(String::from)).is_err());
+        assert!(
+            Args::parse(
+                ["state.lle.ron", "--steps", "10", "--checkpoint-every", "0"].map(String::from)
+            )
+            .is_err()
+        );
+        assert!(Args::parse(["--help"].map(String::from)).unwrap().is_none());
+    }
+}
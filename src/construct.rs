@@ -1,5 +1,205 @@
-#[cfg(target_arch = "wasm32")]
-pub type App = crate::controller::App;
+use ui_traits::DisplayStr;
 
-#[cfg(not(target_arch = "wasm32"))]
-pub type App = crate::controller::gencprt::App;
+use crate::app::{APP_NAME, ModelApp};
+
+/// Expands `$body` once per [`Model`], with `$app` aliased to the model's `GenApp`
+macro_rules! with_model {
+    ($model:expr, $app:ident => $body:expr) => {
+        match $model {
+            $crate::construct::Model::Lle => {
+                type $app = $crate::controller::App;
+                $body
+            }
+            $crate::construct::Model::Fp => {
+                type $app = $crate::controller::fp::App;
+                $body
+            }
+            $crate::construct::Model::Clle => {
+                type $app = $crate::controller::clle::App;
+                $body
+            }
+            $crate::construct::Model::Cprt => {
+                type $app = $crate::controller::cprt::App;
+                $body
+            }
+            $crate::construct::Model::Cprt2 => {
+                type $app = $crate::controller::cprt2::App;
+                $body
+            }
+            $crate::construct::Model::Disper => {
+                type $app = $crate::controller::disper::App;
+                $body
+            }
+            $crate::construct::Model::Disper2 => {
+                type $app = $crate::controller::disper2::App;
+                $body
+            }
+            $crate::construct::Model::SelfPump => {
+                type $app = $crate::controller::self_pump::App;
+                $body
+            }
+            $crate::construct::Model::InterleaveSelfPump => {
+                type $app = $crate::controller::interleave_self_pump::App;
+                $body
+            }
+            $crate::construct::Model::PulsePump => {
+                type $app = $crate::controller::pulse_pump::App;
+                $body
+            }
+            $crate::construct::Model::DualPulsePump => {
+                type $app = $crate::controller::dual_pulse_pump::App;
+                $body
+            }
+            $crate::construct::Model::GenCprt => {
+                type $app = $crate::controller::gencprt::App;
+                $body
+            }
+        }
+    };
+}
+pub(crate) use with_model;
+
+/// Storage key of the model selected in the start window
+const MODEL_KEY: &str = "LLE Simulator model";
+
+/// Models that can be picked in the start window
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, enum_iterator::Sequence,
+)]
+pub enum Model {
+    Lle,
+    Fp,
+    Clle,
+    Cprt,
+    Cprt2,
+    Disper,
+    Disper2,
+    SelfPump,
+    InterleaveSelfPump,
+    PulsePump,
+    DualPulsePump,
+    GenCprt,
+}
+
+impl Default for Model {
+    #[cfg(target_arch = "wasm32")]
+    fn default() -> Self {
+        Self::Lle
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default() -> Self {
+        Self::GenCprt
+    }
+}
+
+impl DisplayStr for Model {
+    fn desc(&self) -> &str {
+        match self {
+            Model::Lle => "LLE",
+            Model::Fp => "Fabry-Perot LLE",
+            Model::Clle => "Coupled LLE",
+            Model::Cprt => "Coupled ring",
+            Model::Cprt2 => "Coupled ring (decaying coupling)",
+            Model::Disper => "LLE with dispersion profile",
+            Model::Disper2 => "LLE with mode coupling",
+            Model::SelfPump => "Self-pumped LLE",
+            Model::InterleaveSelfPump => "Interleaved self-pumped LLE",
+            Model::PulsePump => "Pulse-pumped LLE",
+            Model::DualPulsePump => "Dual pulse-pumped LLE",
+            Model::GenCprt => "General coupled ring",
+        }
+    }
+}
+
+impl Model {
+    /// Short stable identifier, used for storage keys and file names
+    pub fn id(&self) -> &'static str {
+        match self {
+            Model::Lle => "lle",
+            Model::Fp => "fp",
+            Model::Clle => "clle",
+            Model::Cprt => "cprt",
+            Model::Cprt2 => "cprt2",
+            Model::Disper => "dis",
+            Model::Disper2 => "dis2",
+            Model::SelfPump => "slle",
+            Model::InterleaveSelfPump => "islle",
+            Model::PulsePump => "plle",
+            Model::DualPulsePump => "dplle",
+            Model::GenCprt => "gencprt",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        enum_iterator::all::<Self>().find(|m| m.id() == id)
+    }
+
+    /// Every model keeps its own persisted state
+    pub(crate) fn storage_key(&self) -> String {
+        format!("{APP_NAME} {}", self.id())
+    }
+
+    /// Key of the state saved before the model could be picked, by the default model only
+    pub(crate) fn legacy_storage_key(&self) -> Option<&'static str> {
+        (*self == Self::default()).then_some(APP_NAME)
+    }
+
+    fn build(
+        self,
+        storage: Option<&dyn eframe::Storage>,
+        #[cfg(feature = "gpu")] render_state: &eframe::egui_wgpu::RenderState,
+    ) -> Box<dyn ModelApp> {
+        with_model!(self, A => Box::new(A::new(
+            self,
+            storage,
+            #[cfg(feature = "gpu")]
+            render_state.clone(),
+        )))
+    }
+}
+
+/// The application shell, hosting the model picked in the start window
+pub struct App {
+    model: Model,
+    app: Box<dyn ModelApp>,
+}
+
+impl App {
+    /// Called once before the first frame.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let model: Model = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, MODEL_KEY))
+            .unwrap_or_default();
+        let app = model.build(
+            cc.storage,
+            #[cfg(feature = "gpu")]
+            cc.wgpu_render_state.as_ref().unwrap(),
+        );
+        Self { model, app }
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.app.update(ctx, frame);
+        if let Some(model) = self.app.take_switch() {
+            if let Some(storage) = frame.storage_mut() {
+                self.app.save(storage);
+            }
+            self.model = model;
+            self.app = model.build(
+                frame.storage(),
+                #[cfg(feature = "gpu")]
+                frame.wgpu_render_state().unwrap(),
+            );
+            self.app.open_start_window();
+            ctx.request_repaint();
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.app.save(storage);
+        eframe::set_value(storage, MODEL_KEY, &self.model);
+    }
+}
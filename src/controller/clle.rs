@@ -13,6 +13,7 @@ pub type App = crate::app::GenApp<CoupleLleController, CLleSolver, [crate::drawe
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct CoupleLleController {
     pub(crate) basic: LleController,
@@ -107,6 +108,10 @@ impl Controller<CLleSolver> for CoupleLleController {
     fn steps(&self) -> u32 {
         self.basic.steps.get_value()
     }
+
+    fn cw_pump(&self) -> Option<CwPump> {
+        Some(self.basic.pump_line())
+    }
 }
 
 impl<'a> SharedState<'a> for CLleSolver {
@@ -154,4 +159,11 @@ impl Simulator for CLleSolver {
     fn cur_step(&self) -> u32 {
         <Self as lle::Evolver<f64>>::cur_step(self)
     }
+    fn step_dist(&self) -> f64 {
+        self.component1.step_dist
+    }
+    fn set_step_dist(&mut self, step_dist: f64) {
+        self.component1.step_dist = step_dist;
+        self.component2.step_dist = step_dist;
+    }
 }
@@ -1,7 +1,7 @@
 use lle::{DiffOrder, Freq, LinearOp, NoneOp, Step, num_complex::Complex64};
 use num_traits::{Zero, zero};
 
-use super::{Controller, Property};
+use super::{Controller, CwPump, Property};
 
 #[allow(unused)]
 pub type App =
@@ -14,6 +14,7 @@ pub type App =
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct CosDispersionProperty {
     center_pos: Property<f64>,
@@ -83,6 +84,7 @@ pub type LleSolver<NL> = lle::LleSolver<
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct DisperLleController {
     pub(crate) basic: super::LleController,
@@ -121,6 +123,10 @@ impl<NL: Default + lle::NonLinearOp<f64>> Controller<LleSolver<NL>> for DisperLl
     fn steps(&self) -> u32 {
         self.basic.steps.get_value()
     }
+
+    fn cw_pump(&self) -> Option<CwPump> {
+        Some(self.basic.pump_line())
+    }
     fn sync_paras(&mut self, engine: &mut LleSolver<NL>) {
         engine.constant = Complex64::from(self.basic.pump.get_value());
         engine.step_dist = self.basic.step_dist.get_value();
@@ -4,7 +4,7 @@ use lle::{
 };
 use num_traits::{Zero, zero};
 
-use super::{Controller, Property};
+use super::{Controller, CwPump, Property};
 
 #[allow(unused)]
 pub type App = crate::app::GenApp<
@@ -20,6 +20,7 @@ pub type App = crate::app::GenApp<
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct CosDispersionProperty2 {
     center_pos: Property<f64>,
@@ -87,6 +88,7 @@ pub type LleSolver<NL, C> = lle::LleSolver<f64, Vec<Complex64>, LinearOpCached<f
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct DisperLleController2 {
     pub(crate) basic: super::LleController,
@@ -131,6 +133,10 @@ impl<NL: Default + lle::NonLinearOp<f64>> Controller<LleSolver<NL, Complex64>>
     fn steps(&self) -> u32 {
         self.basic.steps.get_value()
     }
+
+    fn cw_pump(&self) -> Option<CwPump> {
+        Some(self.basic.pump_line())
+    }
     fn sync_paras(&mut self, engine: &mut LleSolver<NL, Complex64>) {
         engine.constant = Complex64::from(self.basic.pump.get_value());
         engine.step_dist = self.basic.step_dist.get_value();
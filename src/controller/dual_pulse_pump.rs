@@ -1,6 +1,7 @@
 use lle::StaticConstOp;
 
 use super::*;
+use crate::preview::LegacyTarget;
 
 #[allow(unused)]
 pub type App = crate::app::GenApp<
@@ -19,6 +20,7 @@ pub type LleSolver<NL> = lle::LleSolver<f64, Vec<Complex64>, LinearOp, NL, lle::
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct DualPulsePumpLleController {
     pub(crate) alpha: Property<f64>,
@@ -55,6 +57,7 @@ impl std::default::Default for DualPulsePumpLleController {
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct SinglePump {
     pub(crate) peak: Property<f64>,
@@ -78,6 +81,7 @@ impl std::default::Default for SinglePump {
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub(crate) struct Pump {
     pub(crate) pulse1: SinglePump,
@@ -169,6 +173,13 @@ impl<NL: lle::NonLinearOp<f64> + Default> Controller<LleSolver<NL>> for DualPuls
         engine.step_dist = step_dist;
     }
 
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        match target {
+            LegacyTarget::Pump => &["pump.pulse1.peak", "pump.pulse2.peak"],
+            t => t.paths(),
+        }
+    }
+
     fn steps(&self) -> u32 {
         self.steps.get_value()
     }
@@ -4,7 +4,8 @@ use lle::{DiffOrder, LinearOpCached, NoneOp, StaticLinearOp, num_complex::Comple
 use ops::PumpFreq;
 use state::CoupleInfo;
 
-use super::{Controller, Property, cprt2::CoupleStrength};
+use super::{Controller, CwPump, Property, cprt2::CoupleStrength};
+use crate::preview::LegacyTarget;
 
 pub use walkoff::WalkOff;
 
@@ -24,7 +25,7 @@ pub type App = crate::app::GenApp<
     GenCprtController,
     WalkOff<LleSolver<lle::SPhaMod, NoneOp<f64>, PumpFreq>>,
     crate::drawer::ViewField<state::State>,
-    crate::preview::BasicPreviewTarget,
+    crate::preview::ParamTarget,
     debugger::Debugger,
 >;
 
@@ -37,6 +38,7 @@ pub type LleSolver<NL, C, CF> = lle::LleSolver<f64, state::State, LinearOpCached
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct GenCprtController {
     pub(crate) alpha: Property<f64>,
@@ -89,6 +91,7 @@ impl Default for GenCprtController {
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct GenCprtDisperSubController {
     pub(crate) linear: Property<f64>,
@@ -138,6 +141,7 @@ impl Default for GenCprtDisperSubController {
     serde::Serialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct GenCprtPumpSubController {
     pub(crate) mode_number: Property<i32>,
@@ -192,9 +196,25 @@ impl<NL: Default + lle::NonLinearOp<f64>> Controller<LleSolver<NL, NoneOp<f64>,
             .build()
     }
 
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        match target {
+            LegacyTarget::Pump => &["pump.amplitude"],
+            LegacyTarget::Linear => &["disper.linear"],
+            t => t.paths(),
+        }
+    }
+
     fn steps(&self) -> u32 {
         self.steps.get_value()
     }
+
+    fn cw_pump(&self) -> Option<CwPump> {
+        Some(CwPump {
+            field: 0,
+            mode: self.pump.mode_number.get_value(),
+            amplitude: self.pump.amplitude.get_value(),
+        })
+    }
     fn sync_paras(&mut self, engine: &mut LleSolver<NL, NoneOp<f64>, PumpFreq>) {
         use lle::Evolver;
         engine.get_raw_state_mut().cp = self.disper.get_coup_info();
@@ -470,6 +470,22 @@ impl State {
     }
 }
 
+impl crate::controller::Fields for State {
+    type Field = Self;
+    fn fields(&self) -> Vec<Self::Field> {
+        vec![self.clone()]
+    }
+}
+
+/// The two fields, one in each half of `data`
+impl crate::controller::Components for State {
+    fn components_mut(&mut self) -> Vec<&mut [Complex64]> {
+        let len = self.data.len();
+        let (a, b) = self.data.split_at_mut(len / 2);
+        vec![a, b]
+    }
+}
+
 impl AsRef<[Complex<f64>]> for State {
     fn as_ref(&self) -> &[Complex<f64>] {
         &self.data
@@ -4,7 +4,7 @@ use lle::{ConstOp, DiffOrder, Evolver, StaticLinearOp, Step, num_complex::Comple
 
 use crate::{
     FftSource,
-    controller::{Controller, SharedState, Simulator, StoreState},
+    controller::{Components, Controller, SharedState, Simulator, StoreState},
     random::RandomNoise,
 };
 
@@ -180,7 +180,7 @@ impl<
 }
 
 impl<
-    S: FftSource + for<'a> serde::Deserialize<'a> + serde::Serialize,
+    S: FftSource + for<'a> serde::Deserialize<'a> + serde::Serialize + Components,
     L: lle::LinearOp<f64>,
     NL: lle::NonLinearOp<f64>,
     C: ConstOp<f64>,
@@ -223,6 +223,12 @@ impl<
     fn cur_step(&self) -> u32 {
         Simulator::cur_step(&self.core)
     }
+    fn step_dist(&self) -> f64 {
+        self.core.step_dist
+    }
+    fn set_step_dist(&mut self, step_dist: f64) {
+        self.core.step_dist = step_dist;
+    }
 }
 
 #[cfg(test)]
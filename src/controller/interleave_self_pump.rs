@@ -1,6 +1,7 @@
 use egui::mutex::RwLock;
 
 use super::*;
+use crate::preview::LegacyTarget;
 
 #[allow(unused)]
 pub type App = crate::app::GenApp<
@@ -17,6 +18,7 @@ pub type App = crate::app::GenApp<
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct InterleaveSelfPumpLleController {
     pub(crate) alpha: Property<f64>,
@@ -53,6 +55,7 @@ impl std::default::Default for InterleaveSelfPumpLleController {
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct InterleaveSelfPump {
     pub(crate) const_pump: Property<f64>,
@@ -184,6 +187,17 @@ impl<NL: lle::NonLinearOp<f64> + Default> Controller<LleSolver<NL>>
         engine.step_dist = step_dist;
     }
 
+    fn sync_history(&self, src: &LleSolver<NL>, engine: &mut LleSolver<NL>) {
+        engine.constant.op1.follow(&src.constant.op1);
+    }
+
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        match target {
+            LegacyTarget::Pump => &["pump.loop_loss"],
+            t => t.paths(),
+        }
+    }
+
     fn steps(&self) -> u32 {
         self.steps.get_value()
     }
@@ -85,6 +85,9 @@ impl<NL: Default + lle::NonLinearOp<f64>> Controller<LleSolver<NL, Complex64, No
     fn sync_paras(&mut self, engine: &mut LleSolver<NL, Complex64, NoneOp<f64>>) {
         crate::util::synchronize_properties(self, engine);
     }
+    fn cw_pump(&self) -> Option<CwPump> {
+        Some(self.pump_line())
+    }
 }
 
 #[derive(
@@ -95,6 +98,7 @@ impl<NL: Default + lle::NonLinearOp<f64>> Controller<LleSolver<NL, Complex64, No
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct LleController {
     pub(crate) alpha: Property<f64>,
@@ -132,6 +136,17 @@ impl Default for LleController {
     }
 }
 
+impl LleController {
+    /// The CW pump at the center of the only or first field
+    pub(crate) fn pump_line(&self) -> CwPump {
+        CwPump {
+            field: 0,
+            mode: 0,
+            amplitude: self.pump.get_value(),
+        }
+    }
+}
+
 impl<
     'a,
     S: FftSource,
@@ -151,7 +166,7 @@ impl<
 }
 
 impl<
-    S: FftSource + for<'a> serde::Deserialize<'a> + serde::Serialize,
+    S: FftSource + for<'a> serde::Deserialize<'a> + serde::Serialize + Components,
     L: lle::LinearOp<f64>,
     NL: lle::NonLinearOp<f64>,
     C: ConstOp<f64>,
@@ -198,6 +213,12 @@ where
     fn cur_step(&self) -> u32 {
         <Self as lle::Evolver<f64>>::cur_step(self)
     }
+    fn step_dist(&self) -> f64 {
+        self.step_dist
+    }
+    fn set_step_dist(&mut self, step_dist: f64) {
+        self.step_dist = step_dist;
+    }
 }
 
 pub fn dispersion_line<L: lle::LinearOp<f64>>(l: L, dim: usize, scale: f64) -> PlotElement {
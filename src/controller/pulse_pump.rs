@@ -1,6 +1,7 @@
 use lle::StaticConstOp;
 
 use super::*;
+use crate::preview::LegacyTarget;
 
 #[allow(unused)]
 pub type App =
@@ -16,6 +17,7 @@ pub type LleSolver<NL> = lle::LleSolver<f64, Vec<Complex64>, LinearOp, NL, lle::
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct PulsePumpLleController {
     pub(crate) alpha: Property<f64>,
@@ -52,6 +54,7 @@ impl std::default::Default for PulsePumpLleController {
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub(crate) struct Pump {
     pub(crate) peak: Property<f64>,
@@ -133,6 +136,13 @@ impl<NL: lle::NonLinearOp<f64> + Default> Controller<LleSolver<NL>> for PulsePum
         engine.step_dist = step_dist;
     }
 
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        match target {
+            LegacyTarget::Pump => &["pump.peak"],
+            t => t.paths(),
+        }
+    }
+
     fn steps(&self) -> u32 {
         self.steps.get_value()
     }
@@ -1,6 +1,7 @@
 use egui::mutex::RwLock;
 
 use super::*;
+use crate::preview::LegacyTarget;
 
 #[allow(unused)]
 pub type App =
@@ -14,6 +15,7 @@ pub type App =
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct SelfPumpLleController {
     pub(crate) alpha: Property<f64>,
@@ -50,6 +52,7 @@ impl std::default::Default for SelfPumpLleController {
     serde::Deserialize,
     ui_traits::ControllerStartWindow,
     ui_traits::ControllerUI,
+    ui_traits::Params,
 )]
 pub struct SelfPump {
     pub(crate) const_pump: Property<f64>,
@@ -148,6 +151,17 @@ impl<NL: lle::NonLinearOp<f64> + Default> Controller<LleSolver<NL>> for SelfPump
         engine.step_dist = step_dist;
     }
 
+    fn sync_history(&self, src: &LleSolver<NL>, engine: &mut LleSolver<NL>) {
+        engine.constant.op1.follow(&src.constant.op1);
+    }
+
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        match target {
+            LegacyTarget::Pump => &["pump.loop_loss"],
+            t => t.paths(),
+        }
+    }
+
     fn steps(&self) -> u32 {
         self.steps.get_value()
     }
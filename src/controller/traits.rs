@@ -1,7 +1,12 @@
-use crate::random::RandomNoise;
+use crate::{preview::LegacyTarget, random::RandomNoise};
 
 pub trait Controller<E>:
-    'static + Send + Sync + ui_traits::ControllerStartWindow + ui_traits::ControllerUI
+    'static
+    + Send
+    + Sync
+    + ui_traits::ControllerStartWindow
+    + ui_traits::ControllerUI
+    + ui_traits::Params
 {
     const EXTENSION: &'static str;
     type Dispersion: lle::LinearOp<f64>;
@@ -22,7 +27,26 @@ pub trait Controller<E>:
         crate::config::config(dim, self, ui)
     }
     fn sync_paras(&mut self, engine: &mut E);
+    /// Carry over what `engine` keeps of the past besides its state, like the delay line
+    /// of a self pump, from `src`, to run a copy of `src` from where it stands
+    fn sync_history(&self, _src: &E, _engine: &mut E) {}
     fn steps(&self) -> u32;
+    /// The pump if it drives a single line, for the figures of merit of the comb
+    fn cw_pump(&self) -> Option<CwPump> {
+        None
+    }
+    /// Paths of the parameters a preview target of an older save offsets
+    fn legacy_paths(target: LegacyTarget) -> &'static [&'static str] {
+        target.paths()
+    }
+}
+
+/// A CW pump of amplitude `F` on the line `mode` of the component `field` of the state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CwPump {
+    pub field: usize,
+    pub mode: i32,
+    pub amplitude: f64,
 }
 
 /// For monitor and visualize state
@@ -41,14 +65,57 @@ pub trait StoreState {
         + Sync
         + std::fmt::Debug
         + serde::Serialize
-        + for<'a> serde::Deserialize<'a>;
+        + for<'a> serde::Deserialize<'a>
+        + Components;
     fn get_owned_state(&self) -> <Self as StoreState>::OwnedState;
     fn set_owned_state(&mut self, state: <Self as StoreState>::OwnedState);
     fn default_state(dim: usize) -> <Self as StoreState>::OwnedState;
 }
 
+/// Owned state seen as the fields shown in separate views
+pub trait Fields {
+    type Field: crate::FftSource;
+    fn fields(&self) -> Vec<Self::Field>;
+}
+
+impl Fields for Vec<lle::num_complex::Complex64> {
+    type Field = Self;
+    fn fields(&self) -> Vec<Self::Field> {
+        vec![self.clone()]
+    }
+}
+
+impl<T: Fields, const L: usize> Fields for [T; L] {
+    type Field = T::Field;
+    fn fields(&self) -> Vec<Self::Field> {
+        self.iter().flat_map(Fields::fields).collect()
+    }
+}
+
+/// Owned state seen as its components in the real domain, to build it from profiles
+pub trait Components {
+    fn components_mut(&mut self) -> Vec<&mut [lle::num_complex::Complex64]>;
+}
+
+impl Components for Vec<lle::num_complex::Complex64> {
+    fn components_mut(&mut self) -> Vec<&mut [lle::num_complex::Complex64]> {
+        vec![self.as_mut_slice()]
+    }
+}
+
+impl<T: Components, const L: usize> Components for [T; L] {
+    fn components_mut(&mut self) -> Vec<&mut [lle::num_complex::Complex64]> {
+        self.iter_mut()
+            .flat_map(Components::components_mut)
+            .collect()
+    }
+}
+
 pub trait Simulator: 'static + for<'a> SharedState<'a> + StoreState + Send + Sync {
     fn add_rand(&mut self, random: &mut RandomNoise);
     fn run(&mut self, steps: u32);
     fn cur_step(&self) -> u32;
+    /// Time step `Δt` of the solver, overridden by the adaptive step control
+    fn step_dist(&self) -> f64;
+    fn set_step_dist(&mut self, step_dist: f64);
 }
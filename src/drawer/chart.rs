@@ -28,6 +28,9 @@ pub struct LleChart<S: FftSource = Vec<Complex64>> {
     pub(crate) drawer: Option<ColorMapDrawer>,
     #[serde(skip)]
     pub(crate) additional: Option<Vec<PlotElement>>,
+    /// marks the solitons, on the real domain only
+    #[serde(default)]
+    pub(crate) solitons: Option<SolitonFinder>,
 }
 
 impl<S: FftSource> LleChart<S> {
@@ -36,7 +39,7 @@ impl<S: FftSource> LleChart<S> {
         self.additional.get_or_insert_default().push(PlotElement {
             y: s,
             x: plot_data.x.clone(),
-            legend: None,
+            legend: plot_data.legend.clone(),
             style: plot_data.style,
         })
     }
@@ -61,6 +64,7 @@ impl<S: FftSource> Clone for LleChart<S> {
             show_history: self.show_history,
             drawer: None,
             additional: None,
+            solitons: self.solitons.clone(),
         }
     }
 }
@@ -76,6 +80,7 @@ impl<S: FftSource + Debug> Debug for LleChart<S> {
             .field("rf_fft_global_norm", &self.rf_fft_global_norm)
             .field("show_history", &self.drawer)
             .field("additional", &self.additional.is_some())
+            .field("solitons", &self.solitons)
             .finish()
     }
 }
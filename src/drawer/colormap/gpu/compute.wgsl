@@ -36,6 +36,7 @@ var<uniform> uniforms: Uniforms;
 var<storage, read> colormap: array<u32>;
 
 const RAW_REDUCE_WG: u32 = 64u;
+const RAW_ROWS_WG: u32 = 64u;
 var<workgroup> wg_raw_min: array<f32, RAW_REDUCE_WG>;
 var<workgroup> wg_raw_max: array<f32, RAW_REDUCE_WG>;
 
@@ -45,6 +46,12 @@ fn main_raw(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compute_raw(global_id);
 }
 
+// Entry point for the raw components scanning their whole row, one invocation per row.
+@compute @workgroup_size(RAW_ROWS_WG, 1, 1)
+fn main_raw_rows(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    compute_raw_rows(global_id);
+}
+
 // Entry point for stage-1 raw min/max reduction over tiles.
 @compute @workgroup_size(RAW_REDUCE_WG, 1, 1)
 fn main_raw_reduce_stage1(
@@ -140,7 +147,7 @@ fn compute_raw(global_id: vec3<u32>) {
         mm = vec2<f32>(0.0, 1.0);
     }
     let denom = max(mm.y - mm.x, 1e-12);
-    var value = raw_scalar_value(raw_data[index]);
+    var value = raw_scalar_value(index);
     if !is_finite_f32(value) {
         cache_data[index] = sample_colormap(0.0);
         return;
@@ -149,8 +156,40 @@ fn compute_raw(global_id: vec3<u32>) {
     cache_data[index] = sample_colormap(value);
 }
 
-// Converts one complex raw sample into the selected scalar component.
-fn raw_scalar_value(src: vec2<f32>) -> f32 {
+// Writes the unwrapped phase or the normalized power of every sample of a row to rf_values,
+// scanning the row once for the cumulative phase or the peak power.
+fn compute_raw_rows(global_id: vec3<u32>) {
+    if global_id.x >= uniforms.height {
+        return;
+    }
+    let row = global_id.x * uniforms.width;
+    if uniforms.raw_component == 5u {
+        var phase = 0.0;
+        var prev = vec2<f32>(1.0, 0.0);
+        for (var i = 0u; i < uniforms.width; i = i + 1u) {
+            let v = raw_data[row + i];
+            phase = phase + phase_step(prev, v);
+            rf_values[row + i] = phase;
+            prev = v;
+        }
+    } else if uniforms.raw_component == 7u {
+        var peak = 0.0;
+        for (var i = 0u; i < uniforms.width; i = i + 1u) {
+            let v = raw_data[row + i];
+            peak = max(peak, dot(v, v));
+        }
+        for (var i = 0u; i < uniforms.width; i = i + 1u) {
+            let v = raw_data[row + i];
+            rf_values[row + i] = select(0.0, dot(v, v) / peak, peak > 0.0);
+        }
+    }
+}
+
+// Converts the raw sample at `index` into the selected scalar component,
+// matching `Component::extract` on its row.
+// The unwrapped phase and the normalized power are read from the row scan of `main_raw_rows`.
+fn raw_scalar_value(index: u32) -> f32 {
+    let src = raw_data[index];
     var value = 0.0;
     if uniforms.raw_component == 0u {
         value = src.x;
@@ -158,16 +197,38 @@ fn raw_scalar_value(src: vec2<f32>) -> f32 {
         value = src.y;
     } else if uniforms.raw_component == 2u {
         value = length(src);
-    } else {
+    } else if uniforms.raw_component == 3u {
         value = atan2(src.y, src.x);
+    } else if uniforms.raw_component == 4u {
+        value = dot(src, src);
+    } else if uniforms.raw_component == 6u {
+        let col = index % uniforms.width;
+        let row = index - col;
+        let prev = raw_data[row + (col + uniforms.width - 1u) % uniforms.width];
+        let next = raw_data[row + (col + 1u) % uniforms.width];
+        value = 0.5 * phase_step(prev, next);
+    } else {
+        value = rf_values[index];
     }
     if uniforms.raw_db_scale != 0u {
-        value = 20.0 * log(value) / log(10.0);
+        // powers in 10 log10, amplitudes in 20 log10
+        let db = select(20.0, 10.0, uniforms.raw_component == 4u || uniforms.raw_component == 7u);
+        value = db * log(value) / log(10.0);
     }
     return value;
 }
 
+// Phase of `b` relative to `a`, in (-pi, pi], zero when either vanishes.
+fn phase_step(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let p = vec2<f32>(b.x * a.x + b.y * a.y, b.y * a.x - b.x * a.y);
+    if p.x == 0.0 && p.y == 0.0 {
+        return 0.0;
+    }
+    return atan2(p.y, p.x);
+}
+
 // Reduces one workgroup tile to a partial min/max pair in rf_fft_state.
+// Like compute_raw, it reads the row components from the scan of main_raw_rows.
 fn compute_raw_reduce_stage1(
     global_id: vec3<u32>,
     local_id: vec3<u32>,
@@ -178,7 +239,7 @@ fn compute_raw_reduce_stage1(
     var local_min = 1e30;
     var local_max = -1e30;
     if idx < total {
-        let value = raw_scalar_value(raw_data[idx]);
+        let value = raw_scalar_value(idx);
         if is_finite_f32(value) {
             local_min = value;
             local_max = value;
@@ -243,6 +243,7 @@ impl Drawer {
 
         let (
             compute_bind_group,
+            compute_pipeline_raw_rows,
             compute_pipeline_raw_reduce_stage1,
             compute_pipeline_raw_reduce_stage2,
             compute_pipeline_raw,
@@ -347,6 +348,7 @@ impl Drawer {
         let resource = RenderResources {
             uniforms,
             compute_pipeline_layout,
+            compute_pipeline_raw_rows,
             compute_pipeline_raw_reduce_stage1,
             compute_pipeline_raw_reduce_stage2,
             compute_pipeline_raw,
@@ -724,6 +726,7 @@ mod tests {
 
             let (
                 _bind_group,
+                _raw_rows,
                 _raw_reduce_stage1,
                 _raw_reduce_stage2,
                 _raw,
@@ -1,10 +1,15 @@
 use super::*;
 
+/// Workgroup sizes of the raw passes, `RAW_REDUCE_WG` and `RAW_ROWS_WG` of `compute.wgsl`
+const RAW_REDUCE_WG: u32 = 64;
+const RAW_ROWS_WG: u32 = 64;
+
 #[derive(Debug)]
 pub struct RenderResources {
     pub(crate) uniforms: Uniforms,
     // compute stuff
     pub(crate) compute_pipeline_layout: wgpu::PipelineLayout,
+    pub(crate) compute_pipeline_raw_rows: wgpu::ComputePipeline,
     pub(crate) compute_pipeline_raw_reduce_stage1: wgpu::ComputePipeline,
     pub(crate) compute_pipeline_raw_reduce_stage2: wgpu::ComputePipeline,
     pub(crate) compute_pipeline_raw: wgpu::ComputePipeline,
@@ -94,6 +99,7 @@ impl RenderResources {
 
             (
                 self.compute_bind_group,
+                self.compute_pipeline_raw_rows,
                 self.compute_pipeline_raw_reduce_stage1,
                 self.compute_pipeline_raw_reduce_stage2,
                 self.compute_pipeline_raw,
@@ -156,8 +162,18 @@ impl RenderResources {
         let dispatch_y = self.uniforms.height.div_ceil(WORKGROUP_SIZE.1 as u32);
         if self.uniforms.compute_mode == 0 {
             let total = self.uniforms.width.saturating_mul(self.uniforms.height);
-            let raw_reduce_wg = 64u32;
-            let partial_count = total.div_ceil(raw_reduce_wg);
+            let partial_count = total.div_ceil(RAW_REDUCE_WG);
+            // unwrapped phase and normalized power, depending on the whole row:
+            // scanned once per row, before both the range reduction and the colormap read them
+            if matches!(self.uniforms.raw_component, 5 | 7) {
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("raw rows"),
+                    timestamp_writes: None,
+                });
+                cpass.set_pipeline(&self.compute_pipeline_raw_rows);
+                cpass.set_bind_group(0, &self.compute_bind_group, &[]);
+                cpass.dispatch_workgroups(self.uniforms.height.div_ceil(RAW_ROWS_WG).max(1), 1, 1);
+            }
             {
                 let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                     label: Some("raw reduce stage1"),
@@ -288,6 +304,7 @@ impl RenderResources {
         wgpu::ComputePipeline,
         wgpu::ComputePipeline,
         wgpu::ComputePipeline,
+        wgpu::ComputePipeline,
         FftRuntimeConfig,
     ) {
         let cfg = fft_runtime_config(&device.limits());
@@ -367,6 +384,18 @@ impl RenderResources {
             label: Some("Compute Bind Group"),
         });
 
+        let compute_pipeline_raw_rows =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Compute Pipeline Raw Rows"),
+                layout: Some(pipeline_layout),
+                module: shader,
+                entry_point: Some("main_raw_rows"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &fft_override_constants,
+                    ..Default::default()
+                },
+                cache: None,
+            });
         let compute_pipeline_raw_reduce_stage1 =
             device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
                 label: Some("Compute Pipeline Raw Reduce Stage1"),
@@ -465,6 +494,7 @@ impl RenderResources {
             });
         (
             compute_bind_group,
+            compute_pipeline_raw_rows,
             compute_pipeline_raw_reduce_stage1,
             compute_pipeline_raw_reduce_stage2,
             compute_pipeline_raw,
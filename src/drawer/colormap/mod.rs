@@ -216,7 +216,16 @@ impl<S: FftSource> LleChart<S> {
                     chart0.kind.show_controller(ui);
                     ui.separator();
                     smarter_bound_controller(&mut chart0.smart_bound, ui);
+                    if chart0.proc.core.fft.is_none() {
+                        ui.separator();
+                        crate::util::show_option(ui, &mut chart0.solitons, "Solitons");
+                    }
                 });
+                if chart0.proc.core.fft.is_none()
+                    && let Some(finder) = chart0.solitons.as_mut()
+                {
+                    finder.show(ui);
+                }
                 ui.horizontal(|ui| chart0.control_ui_history(ui, history));
 
                 match (chart0.show_history, history.get_data_size()) {
@@ -280,7 +289,15 @@ impl<S: FftSource> LleChart<S> {
                     _ => (),
                 };
 
-                let data = chart0.proc.proc(data, running);
+                let field = data;
+                let data = chart0.proc.proc(field, running);
+                if chart0.proc.core.fft.is_none()
+                    && let Some(finder) = chart0.solitons.as_mut()
+                {
+                    finder.update(field.as_ref());
+                    let marks = finder.marks(&data);
+                    chart0.additional.get_or_insert_default().extend(marks);
+                }
                 let mut ui = crate::util::allocate_remained_space(ui);
                 if chart0.drawer.is_some() {
                     let h = (ui.available_height() - ui.spacing().item_spacing.y) / 2.;
@@ -17,6 +17,9 @@ pub use history::History;
 mod processor;
 pub use processor::{FftSource, Process};
 
+mod solitons;
+pub(crate) use solitons::SolitonFinder;
+
 pub mod chart;
 
 use self::chart::LleChart;
@@ -32,6 +35,7 @@ pub(crate) fn default_r_chart<S: FftSource>(index: usize) -> LleChart<S> {
         show_history: false,
         drawer: None,
         additional: None,
+        solitons: None,
     }
 }
 
@@ -46,6 +50,7 @@ pub(crate) fn default_f_chart<S: FftSource>(index: usize) -> LleChart<S> {
         show_history: false,
         drawer: None,
         additional: None,
+        solitons: None,
     }
 }
 
@@ -1,5 +1,3 @@
-use std::iter::Map;
-
 use lle::num_complex::{Complex, ComplexFloat};
 use num_traits::Zero;
 
@@ -206,19 +204,14 @@ impl<S: FftSource> ProcessCore<S> {
             data.as_ref().to_owned()
         };
 
-        if *db_scale {
-            component
-                .extract(data.into_iter())
-                .map(
-                    { |x: f64| T::from_f64(x.log10() * 20.).unwrap_or_else(T::zero) } as fn(_) -> _,
-                )
-                .collect()
-        } else {
-            component
-                .extract(data.into_iter())
-                .map(|x| T::from_f64(x).unwrap_or_else(T::zero))
-                .collect()
-        }
+        component
+            .extract(&data)
+            .into_iter()
+            .map(|x| {
+                let x = if *db_scale { component.db(x) } else { x };
+                T::from_f64(x).unwrap_or_else(T::zero)
+            })
+            .collect()
     }
 
     fn proc_raw_complex(&mut self, data: &S) -> Vec<Complex64> {
@@ -298,6 +291,15 @@ pub enum Component {
     #[default]
     Abs,
     Arg,
+    /// |ψ|²
+    Power,
+    /// phase made continuous along the data
+    UnwrappedPhase,
+    /// derivative of the phase per point, the instantaneous frequency of the field
+    /// or the group delay of the spectrum
+    PhaseDerivative,
+    /// |ψ|² over its peak
+    NormalizedPower,
 }
 
 impl crate::util::DisplayStr for Component {
@@ -307,17 +309,55 @@ impl crate::util::DisplayStr for Component {
             Component::Imag => "Imag",
             Component::Abs => "Abs",
             Component::Arg => "Arg",
+            Component::Power => "Power",
+            Component::UnwrappedPhase => "Unwrapped arg",
+            Component::PhaseDerivative => "d(Arg)",
+            Component::NormalizedPower => "Normalized power",
         }
     }
 }
 
 impl Component {
-    pub fn extract<B: Iterator<Item = Complex64>>(&self, i: B) -> Map<B, fn(Complex64) -> f64> {
+    /// Component of every point of `data`, the phase derivative wrapping around its ends.
+    /// Mirrored by `raw_scalar_value` of the gpu colormap, on every row
+    pub fn extract(&self, data: &[Complex64]) -> Vec<f64> {
+        let each = |f: fn(&Complex64) -> f64| -> Vec<f64> { data.iter().map(f).collect() };
         match self {
-            Component::Real => i.map({ |x| x.re } as fn(Complex64) -> f64),
-            Component::Imag => i.map({ |x| x.im } as fn(Complex64) -> f64),
-            Component::Abs => i.map({ |x| x.abs() } as fn(Complex64) -> f64),
-            Component::Arg => i.map({ |x| x.arg() } as fn(Complex64) -> f64),
+            Component::Real => each(|x| x.re),
+            Component::Imag => each(|x| x.im),
+            Component::Abs => each(|x| x.abs()),
+            Component::Arg => each(|x| x.arg()),
+            Component::Power => each(|x| x.norm_sqr()),
+            Component::UnwrappedPhase => {
+                let mut phase = data.first().map_or(0., |x| x.arg());
+                let mut ret = Vec::with_capacity(data.len());
+                ret.extend(data.first().map(|_| phase));
+                ret.extend(data.windows(2).map(|w| {
+                    phase += (w[1] * w[0].conj()).arg();
+                    phase
+                }));
+                ret
+            }
+            Component::PhaseDerivative => {
+                let len = data.len();
+                (0..len)
+                    .map(|i| (data[(i + 1) % len] * data[(i + len - 1) % len].conj()).arg() / 2.)
+                    .collect()
+            }
+            Component::NormalizedPower => {
+                let peak = data.iter().map(|x| x.norm_sqr()).fold(0., f64::max);
+                data.iter()
+                    .map(|x| if peak > 0. { x.norm_sqr() / peak } else { 0. })
+                    .collect()
+            }
+        }
+    }
+
+    /// `x` of this component in dB, powers taking 10 log10 and amplitudes 20 log10
+    pub fn db(&self, x: f64) -> f64 {
+        match self {
+            Component::Power | Component::NormalizedPower => x.log10() * 10.,
+            _ => x.log10() * 20.,
         }
     }
     /* pub fn extract_f32<B: Iterator<Item = Complex64>>(&self, i: B) -> Map<B, fn(Complex64) -> f32> {
@@ -417,3 +457,62 @@ impl<S: Clone> Delta<S> {
         self.backup = None;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1E-12, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn components() {
+        let data = [Complex64::new(3., 4.), Complex64::new(0., -2.)];
+        assert_close(&Component::Real.extract(&data), &[3., 0.]);
+        assert_close(&Component::Imag.extract(&data), &[4., -2.]);
+        assert_close(&Component::Abs.extract(&data), &[5., 2.]);
+        assert_close(
+            &Component::Arg.extract(&data),
+            &[4f64.atan2(3.), -std::f64::consts::FRAC_PI_2],
+        );
+        assert_close(&Component::Power.extract(&data), &[25., 4.]);
+        assert_close(&Component::NormalizedPower.extract(&data), &[1., 0.16]);
+        assert_close(
+            &Component::NormalizedPower.extract(&[Complex64::zero()]),
+            &[0.],
+        );
+
+        // steps of 0.9 rad wrap around π after the fourth point
+        let ramp = (0..8)
+            .map(|i| Complex64::from_polar(1., 0.9 * i as f64))
+            .collect::<Vec<_>>();
+        let phase = (0..8).map(|i| 0.9 * i as f64).collect::<Vec<_>>();
+        assert_close(&Component::UnwrappedPhase.extract(&ramp), &phase);
+
+        // three turns over a period, the same slope across the ends
+        let len = 16;
+        let slope = 3. * std::f64::consts::TAU / len as f64;
+        let turns = (0..len)
+            .map(|i| Complex64::from_polar(2., slope * i as f64))
+            .collect::<Vec<_>>();
+        assert_close(
+            &Component::PhaseDerivative.extract(&turns),
+            &vec![slope; len],
+        );
+
+        assert!(Component::UnwrappedPhase.extract(&[]).is_empty());
+        assert!(Component::PhaseDerivative.extract(&[]).is_empty());
+
+        assert_close(
+            &[Component::Power.db(100.), Component::Abs.db(10.)],
+            &[20., 20.],
+        );
+        // the discriminants are the `raw_component` of the gpu colormap
+        assert_eq!(Component::Arg as u32, 3);
+        assert_eq!(Component::NormalizedPower as u32, 7);
+    }
+}
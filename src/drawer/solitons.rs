@@ -0,0 +1,233 @@
+use lle::num_complex::Complex64;
+
+use crate::views::PlotElement;
+
+use super::plot_item::Style;
+
+/// A localized peak of `|ψ|²`, positioned and sized in samples of the field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Soliton {
+    pub(crate) position: usize,
+    pub(crate) peak: f64,
+    /// full width at half of the height above the background
+    pub(crate) fwhm: f64,
+    /// where the half height is crossed, past the ends of the field when it wraps around
+    left: f64,
+    right: f64,
+}
+
+impl Soliton {
+    fn covers(&self, position: usize, len: usize) -> bool {
+        let p = position as f64;
+        [p - len as f64, p, p + len as f64]
+            .iter()
+            .any(|p| (self.left..=self.right).contains(p))
+    }
+}
+
+/// Finds the solitons on the real-domain chart and marks them
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SolitonFinder {
+    /// height above the CW background a peak has to reach
+    pub(crate) threshold: f64,
+    #[serde(skip)]
+    pub(crate) found: Vec<Soliton>,
+}
+
+impl Default for SolitonFinder {
+    fn default() -> Self {
+        Self {
+            threshold: 1.,
+            found: Vec::new(),
+        }
+    }
+}
+
+impl SolitonFinder {
+    pub(crate) fn update(&mut self, field: &[Complex64]) {
+        self.found = find_solitons(field, self.threshold);
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.threshold)
+                    .range(0.0..=f64::MAX)
+                    .speed(0.01)
+                    .prefix("above background "),
+            )
+            .on_hover_text("Height of |ψ|² above the CW background a peak has to reach");
+            ui.label(format!("{} solitons", self.found.len()));
+        });
+        if self.found.is_empty() {
+            return;
+        }
+        ui.collapsing("Solitons", |ui| {
+            egui::Grid::new("solitons").striped(true).show(ui, |ui| {
+                ui.label("#");
+                ui.label("Position");
+                ui.label("Peak |ψ|²");
+                ui.label("FWHM");
+                ui.end_row();
+                for (i, s) in self.found.iter().enumerate() {
+                    ui.label(i.to_string());
+                    ui.label(s.position.to_string());
+                    ui.label(format!("{:.4}", s.peak));
+                    ui.label(format!("{:.2}", s.fwhm));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// Marks of the solitons on the chart of `ys`, the field as processed by the chart:
+    /// a line up to every peak and a bar across its half maximum
+    pub(crate) fn marks(&self, ys: &[f64]) -> Vec<PlotElement> {
+        if ys.is_empty() {
+            return Vec::new();
+        }
+        let floor = ys
+            .iter()
+            .copied()
+            .filter(|y| y.is_finite())
+            .fold(f64::INFINITY, f64::min);
+        let at = |x: f64| {
+            let len = ys.len() as f64;
+            let x = x.rem_euclid(len);
+            let (i, t) = (x.floor(), x.fract());
+            let (a, b) = (ys[i as usize], ys[((i + 1.) % len) as usize]);
+            a + (b - a) * t
+        };
+        self.found
+            .iter()
+            .enumerate()
+            .flat_map(|(i, s)| {
+                let p = s.position as f64;
+                let half = (at(s.left) + at(s.right)) / 2.;
+                [
+                    PlotElement {
+                        x: Some(vec![p, p]),
+                        y: vec![floor, ys[s.position]],
+                        legend: Some(format!("#{i}: |ψ|² = {:.4}", s.peak)),
+                        style: Some(Style::default()),
+                    },
+                    PlotElement {
+                        x: Some(vec![s.left, s.right]),
+                        y: vec![half, half],
+                        legend: Some(format!("#{i}: FWHM = {:.2}", s.fwhm)),
+                        style: Some(Style::default()),
+                    },
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Median of `powers`, the CW background under the solitons
+fn background(powers: &[f64]) -> f64 {
+    let mut sorted = powers.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted.get(sorted.len() / 2).copied().unwrap_or(0.)
+}
+
+/// Local maxima of `|ψ|²` higher than `threshold` above the background, on the periodic domain.
+/// Of the maxima within the half maximum of a higher one, like noise on a soliton, only the
+/// higher is kept
+pub(crate) fn find_solitons(field: &[Complex64], threshold: f64) -> Vec<Soliton> {
+    let powers = field.iter().map(|x| x.norm_sqr()).collect::<Vec<_>>();
+    let len = powers.len();
+    let background = background(&powers);
+    let at = |i: isize| powers[i.rem_euclid(len as isize) as usize];
+    let mut candidates = (0..len)
+        .filter(|&j| {
+            let p = powers[j];
+            let j = j as isize;
+            p > background + threshold && p > at(j - 1) && p >= at(j + 1)
+        })
+        .map(|j| {
+            let peak = powers[j];
+            let half = (peak + background) / 2.;
+            // down both sides to the half height, interpolated between the samples
+            let crossing = |dir: isize| {
+                let mut i = j as isize;
+                for _ in 0..len {
+                    let (a, b) = (at(i), at(i + dir));
+                    if b < half {
+                        return i as f64 + dir as f64 * (a - half) / (a - b);
+                    }
+                    i += dir;
+                }
+                i as f64
+            };
+            let (left, right) = (crossing(-1), crossing(1));
+            Soliton {
+                position: j,
+                peak,
+                fwhm: right - left,
+                left,
+                right,
+            }
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.peak.total_cmp(&a.peak));
+    let mut solitons: Vec<Soliton> = Vec::new();
+    for c in candidates {
+        if !solitons.iter().any(|s| s.covers(c.position, len)) {
+            solitons.push(c);
+        }
+    }
+    solitons.sort_by_key(|s| s.position);
+    solitons
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn solitons() {
+        let len = 256;
+        // sech² pulses of half width at half maximum `w`, one of them across the ends
+        let sech2 = |x: f64, w: f64| (x * 1.7627 / (2. * w)).cosh().powi(-2);
+        let pulses = [(40., 4., 9.), (150., 6., 4.), (250., 3., 16.)];
+        let field = (0..len)
+            .map(|i| {
+                let x = i as f64;
+                let p = 1.
+                    + pulses
+                        .iter()
+                        .map(|&(c, w, h)| {
+                            let d =
+                                (x - c + len as f64 / 2.).rem_euclid(len as f64) - len as f64 / 2.;
+                            h * sech2(d, w)
+                        })
+                        .sum::<f64>();
+                Complex64::new(p.sqrt(), 0.)
+            })
+            .collect::<Vec<_>>();
+
+        let found = find_solitons(&field, 1.);
+        assert_eq!(found.len(), 3);
+        for (s, &(c, w, h)) in found.iter().zip(&pulses) {
+            assert_eq!(s.position, c as usize);
+            assert!((s.peak - 1. - h).abs() < 1E-2, "{s:?}");
+            assert!((s.fwhm - 2. * w).abs() < 0.2, "{s:?}");
+        }
+
+        // the small peak is left out above its height
+        assert_eq!(find_solitons(&field, 5.).len(), 2);
+        assert!(find_solitons(&vec![Complex64::new(1., 0.); len], 0.1).is_empty());
+
+        // a ripple on the top of a soliton is no soliton of its own
+        let mut rippled = field.clone();
+        rippled[42] *= 1.2;
+        assert_eq!(find_solitons(&rippled, 1.).len(), 3);
+
+        let finder = SolitonFinder {
+            found,
+            ..Default::default()
+        };
+        let ys = field.iter().map(|x| x.norm_sqr()).collect::<Vec<_>>();
+        assert_eq!(finder.marks(&ys).len(), 6);
+    }
+}
@@ -0,0 +1,377 @@
+//! Small arithmetic expressions of named variables, like `sqrt(alpha)*1.2`
+
+use std::{f64::consts, fmt, str::FromStr};
+
+use anyhow::{Context, bail, ensure};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Bin(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+macro_rules! funcs {
+    ($($name:ident($($arg:ident),+) => $body:expr),* $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[allow(non_camel_case_types)]
+        pub(crate) enum Func {
+            $($name),*
+        }
+
+        impl Func {
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(Func::$name),)*
+                    _ => None,
+                }
+            }
+
+            fn arity(self) -> usize {
+                match self {
+                    $(Func::$name => [$(stringify!($arg)),+].len(),)*
+                }
+            }
+
+            fn call(self, args: &[f64]) -> f64 {
+                match self {
+                    $(Func::$name => {
+                        let [$($arg),+] = args else { unreachable!() };
+                        $(let $arg = *$arg;)+
+                        $body
+                    })*
+                }
+            }
+        }
+    };
+}
+
+funcs! {
+    sqrt(x) => x.sqrt(),
+    exp(x) => x.exp(),
+    ln(x) => x.ln(),
+    log10(x) => x.log10(),
+    abs(x) => x.abs(),
+    sin(x) => x.sin(),
+    cos(x) => x.cos(),
+    tan(x) => x.tan(),
+    asin(x) => x.asin(),
+    acos(x) => x.acos(),
+    atan(x) => x.atan(),
+    sinh(x) => x.sinh(),
+    cosh(x) => x.cosh(),
+    tanh(x) => x.tanh(),
+    sech(x) => x.cosh().recip(),
+    floor(x) => x.floor(),
+    round(x) => x.round(),
+    pow(x, y) => x.powf(y),
+    atan2(y, x) => y.atan2(x),
+    min(x, y) => x.min(y),
+    max(x, y) => x.max(y),
+}
+
+impl Expr {
+    /// Evaluate with the variables given by `vars`
+    pub(crate) fn eval(&self, vars: &dyn Fn(&str) -> Option<f64>) -> anyhow::Result<f64> {
+        Ok(match self {
+            Expr::Num(v) => *v,
+            Expr::Var(name) => vars(name).with_context(|| format!("unknown variable `{name}`"))?,
+            Expr::Neg(e) => -e.eval(vars)?,
+            Expr::Bin(op, l, r) => {
+                let (l, r) = (l.eval(vars)?, r.eval(vars)?);
+                match op {
+                    Op::Add => l + r,
+                    Op::Sub => l - r,
+                    Op::Mul => l * r,
+                    Op::Div => l / r,
+                    Op::Pow => l.powf(r),
+                }
+            }
+            Expr::Call(f, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(vars))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                f.call(&args)
+            }
+        })
+    }
+
+    /// Names of the variables, with repetitions
+    pub(crate) fn vars(&self) -> Vec<&str> {
+        let mut vars = Vec::new();
+        self.visit(&mut |e| {
+            if let Expr::Var(name) = e {
+                vars.push(name.as_str());
+            }
+        });
+        vars
+    }
+
+    /// Rename every variable by `f`
+    pub(crate) fn rename_vars(&mut self, f: &mut dyn FnMut(&str) -> String) {
+        match self {
+            Expr::Num(_) => {}
+            Expr::Var(name) => *name = f(name),
+            Expr::Neg(e) => e.rename_vars(f),
+            Expr::Bin(_, l, r) => {
+                l.rename_vars(f);
+                r.rename_vars(f);
+            }
+            Expr::Call(_, args) => args.iter_mut().for_each(|a| a.rename_vars(f)),
+        }
+    }
+
+    fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a Expr)) {
+        f(self);
+        match self {
+            Expr::Num(_) | Expr::Var(_) => {}
+            Expr::Neg(e) => e.visit(f),
+            Expr::Bin(_, l, r) => {
+                l.visit(f);
+                r.visit(f);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.visit(f)),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let e = parser.expr()?;
+        if let Some(t) = parser.peek() {
+            bail!("unexpected `{t}` in `{s}`");
+        }
+        Ok(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Sym(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(v) => write!(f, "{v}"),
+            Token::Ident(s) => write!(f, "{s}"),
+            Token::Sym(c) => write!(f, "{c}"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            let mut last = ' ';
+            while let Some(&(i, c)) = chars.peek() {
+                let exp_sign = (c == '+' || c == '-') && matches!(last, 'e' | 'E');
+                if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exp_sign) {
+                    break;
+                }
+                last = c;
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let num = &s[start..end];
+            tokens.push(Token::Num(
+                num.parse()
+                    .with_context(|| format!("invalid number `{num}`"))?,
+            ));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Ident(s[start..end].to_string()));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Sym(c));
+            chars.next();
+        } else {
+            bail!("unexpected character `{c}`");
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Sym(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> anyhow::Result<()> {
+        match self.next() {
+            Some(Token::Sym(s)) if s == c => Ok(()),
+            Some(t) => bail!("expected `{c}`, found `{t}`"),
+            None => bail!("expected `{c}`, found the end"),
+        }
+    }
+
+    fn expr(&mut self) -> anyhow::Result<Expr> {
+        let mut l = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(l);
+            };
+            l = Expr::Bin(op, l.into(), self.term()?.into());
+        }
+    }
+
+    fn term(&mut self) -> anyhow::Result<Expr> {
+        let mut l = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok(l);
+            };
+            l = Expr::Bin(op, l.into(), self.unary()?.into());
+        }
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        if self.eat('-') {
+            Ok(Expr::Neg(self.unary()?.into()))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> anyhow::Result<Expr> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Expr::Bin(Op::Pow, base.into(), self.unary()?.into()))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::Num(v)) => Ok(Expr::Num(v)),
+            Some(Token::Sym('(')) => {
+                let e = self.expr()?;
+                self.expect(')')?;
+                Ok(e)
+            }
+            Some(Token::Ident(name)) if self.eat('(') => {
+                let f =
+                    Func::from_name(&name).with_context(|| format!("unknown function `{name}`"))?;
+                let mut args = vec![self.expr()?];
+                while self.eat(',') {
+                    args.push(self.expr()?);
+                }
+                self.expect(')')?;
+                ensure!(
+                    args.len() == f.arity(),
+                    "`{name}` takes {} argument(s), {} given",
+                    f.arity(),
+                    args.len()
+                );
+                Ok(Expr::Call(f, args))
+            }
+            Some(Token::Ident(name)) => Ok(match name.as_str() {
+                "pi" | "π" => Expr::Num(consts::PI),
+                "e" => Expr::Num(consts::E),
+                _ => Expr::Var(name),
+            }),
+            Some(t) => bail!("unexpected `{t}`"),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(s: &str) -> f64 {
+        s.parse::<Expr>()
+            .unwrap()
+            .eval(&|name| match name {
+                "alpha" => Some(4.),
+                "disper.period" => Some(3.),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_and_eval() {
+        assert_eq!(eval("sqrt(alpha)*1.2"), 2.4);
+        assert_eq!(eval("2*disper.period"), 6.);
+        assert_eq!(eval("1 + 2 * 3 - 4 / 2"), 5.);
+        assert_eq!(eval("-2^2"), -4.);
+        assert_eq!(eval("2^3^2"), 512.);
+        assert_eq!(eval("1.5e-3 * 2E+3"), 3.);
+        assert_eq!(eval("max(alpha, -alpha) - min(1, 2)"), 3.);
+        assert_eq!(eval("cos(pi)"), -1.);
+
+        assert!("sqrt(alpha".parse::<Expr>().is_err());
+        assert!("foo(1)".parse::<Expr>().is_err());
+        assert!("pow(1)".parse::<Expr>().is_err());
+        assert!("1 2".parse::<Expr>().is_err());
+        assert!("beta".parse::<Expr>().unwrap().eval(&|_| None).is_err());
+
+        let mut e: Expr = "alpha * period + alpha".parse().unwrap();
+        assert_eq!(e.vars(), ["alpha", "period", "alpha"]);
+        e.rename_vars(&mut |name| format!("disper.{name}"));
+        assert_eq!(e.vars(), ["disper.alpha", "disper.period", "disper.alpha"]);
+    }
+}
@@ -7,6 +7,7 @@ mod config;
 mod controller;
 mod drawer;
 mod easy_mark;
+mod expr;
 mod file;
 mod lle_util;
 mod notify;
@@ -22,5 +23,8 @@ mod construct;
 
 mod app;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+
 pub use crate::construct::App;
 pub use drawer::FftSource;
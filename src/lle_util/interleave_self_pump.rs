@@ -18,6 +18,12 @@ impl Default for InterleaveSelfPumpOp {
 }
 
 impl InterleaveSelfPumpOp {
+    /// Take the delay lines of `src`
+    pub(crate) fn follow(&mut self, src: &Self) {
+        self.channel1.follow(&src.channel1);
+        self.channel2.follow(&src.channel2);
+    }
+
     fn update_state(&self, state: &[Complex64]) {
         self.channel1.update_state(state);
         self.channel2.update_state(state);
@@ -170,6 +170,12 @@ impl SelfPumpOp {
         }
     }
 
+    /// Take the delay line of `src`
+    pub(crate) fn follow(&mut self, src: &Self) {
+        *self.now.write() = *src.now.read();
+        self.cache.write().clone_from(&src.cache.read());
+    }
+
     pub(crate) fn cur_value(&self, len: usize, pos: usize) -> Complex64 {
         let now = *self.now.read();
         self.cache.read()[now * len + pos]
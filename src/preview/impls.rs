@@ -1,273 +1,224 @@
-use crate::controller::dual_pulse_pump::DualPulsePumpLleController;
-use crate::controller::gencprt::GenCprtController;
-use crate::controller::interleave_self_pump::InterleaveSelfPumpLleController;
-use crate::controller::pulse_pump::PulsePumpLleController;
-use crate::controller::self_pump::SelfPumpLleController;
-use crate::controller::{self, LleController, clle::CoupleLleController};
+use ui_traits::Params;
 
 use super::*;
 
-#[derive(
-    Default,
-    Debug,
-    Clone,
-    Copy,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    serde::Deserialize,
-    serde::Serialize,
-    enum_iterator::Sequence,
-)]
-pub enum BasicPreviewTarget {
-    #[default]
+/// Parameter of the controller offset by a preview, by its path like `disper.period`
+#[derive(Default, Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(from = "TargetRepr")]
+pub struct ParamTarget {
+    path: String,
+    /// target of an older save, standing for the parameters its controller offset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    legacy: Option<LegacyTarget>,
+}
+
+/// Also reads the targets saved before they were paths
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum TargetRepr {
+    Path {
+        path: String,
+        #[serde(default)]
+        legacy: Option<LegacyTarget>,
+    },
+    Legacy(LegacyTarget),
+}
+
+/// Preview target of the saves before [`ParamTarget`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum LegacyTarget {
     Alpha,
     Pump,
     Linear,
     StepDist,
 }
 
-impl crate::util::DisplayStr for BasicPreviewTarget {
-    fn desc(&self) -> &str {
+impl LegacyTarget {
+    /// The parameters of the same name, the default of [`Controller::legacy_paths`]
+    pub fn paths(self) -> &'static [&'static str] {
         match self {
-            BasicPreviewTarget::Alpha => "α",
-            BasicPreviewTarget::Pump => "F",
-            BasicPreviewTarget::Linear => "β",
-            BasicPreviewTarget::StepDist => "Δt",
+            LegacyTarget::Alpha => &["alpha"],
+            LegacyTarget::Pump => &["pump"],
+            LegacyTarget::Linear => &["linear"],
+            LegacyTarget::StepDist => &["step_dist"],
         }
     }
 }
 
-impl<S> PreviewTarget<LleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    LleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &LleController, dst: &mut LleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut LleController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
+impl From<TargetRepr> for ParamTarget {
+    fn from(value: TargetRepr) -> Self {
+        match value {
+            TargetRepr::Path { path, legacy } => Self { path, legacy },
+            TargetRepr::Legacy(legacy) => Self {
+                path: legacy.paths()[0].to_string(),
+                legacy: Some(legacy),
+            },
         }
     }
 }
 
-impl<S> PreviewTarget<CoupleLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    CoupleLleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &CoupleLleController, dst: &mut CoupleLleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut CoupleLleController) {
-        let controller = &mut controller.basic;
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
+impl ParamTarget {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            legacy: None,
         }
     }
-}
-
-use controller::cprt::CprtLleController;
-use controller::cprt2::CprtLleController2;
 
-use controller::disper::DisperLleController;
-use controller::disper2::DisperLleController2;
-
-impl<S> PreviewTarget<CprtLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    CprtLleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &CprtLleController, dst: &mut CprtLleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut CprtLleController) {
-        let controller = &mut controller.basic;
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
+    /// The parameters `target` stands for on every controller, like the pump amplitude
+    pub(crate) fn of(target: LegacyTarget) -> Self {
+        Self {
+            path: target.paths()[0].to_string(),
+            legacy: Some(target),
         }
     }
-}
 
-impl<S> PreviewTarget<CprtLleController2, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    CprtLleController2: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &CprtLleController2, dst: &mut CprtLleController2) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut CprtLleController2) {
-        let controller = &mut controller.basic;
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
-        }
+    /// Path among `paths` the target stands for: the exact one, else the only one ending
+    /// with it, else the only one under it, so `step_dist` still finds `basic.step_dist`.
+    /// An empty target stands for the first parameter
+    pub(crate) fn resolve<'a>(&self, paths: &'a [String]) -> Option<&'a String> {
+        resolve(&self.path, paths)
     }
-}
 
-impl<S> PreviewTarget<DisperLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    DisperLleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &DisperLleController, dst: &mut DisperLleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut DisperLleController) {
-        let controller = &mut controller.basic;
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
+    /// Paths among the ones of `controller` the target stands for, several for some older saves
+    pub(crate) fn resolve_all<C: Controller<S>, S>(&self, controller: &mut C) -> Vec<String> {
+        let paths = controller.param_paths();
+        match self.legacy {
+            Some(legacy) => C::legacy_paths(legacy)
+                .iter()
+                .filter_map(|p| resolve(p, &paths).cloned())
+                .collect(),
+            None => self.resolve(&paths).into_iter().cloned().collect(),
         }
     }
 }
 
-impl<S> PreviewTarget<DisperLleController2, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    DisperLleController2: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &DisperLleController2, dst: &mut DisperLleController2) {
-        *dst = src.clone();
-        self.apply(value, dst);
+fn resolve<'a>(path: &str, paths: &'a [String]) -> Option<&'a String> {
+    if path.is_empty() {
+        return paths.first();
     }
-    fn apply(&self, value: f64, controller: &mut DisperLleController2) {
-        let controller = &mut controller.basic;
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
-        }
+    if let Some(p) = paths.iter().find(|p| *p == path) {
+        return Some(p);
     }
-}
-
-impl<S> PreviewTarget<PulsePumpLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    PulsePumpLleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &PulsePumpLleController, dst: &mut PulsePumpLleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
+    let suffix = format!(".{path}");
+    let mut ending = paths.iter().filter(|p| p.ends_with(&suffix));
+    if let (Some(p), None) = (ending.next(), ending.next()) {
+        return Some(p);
     }
-    fn apply(&self, value: f64, controller: &mut PulsePumpLleController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.peak.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
-        }
+    let prefix = format!("{path}.");
+    let mut under = paths.iter().filter(|p| p.starts_with(&prefix));
+    match (under.next(), under.next()) {
+        (Some(p), None) => Some(p),
+        _ => None,
     }
 }
 
-impl<S> PreviewTarget<SelfPumpLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    SelfPumpLleController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &SelfPumpLleController, dst: &mut SelfPumpLleController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut SelfPumpLleController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.loop_loss.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
-        }
+impl DisplayStr for ParamTarget {
+    fn desc(&self) -> &str {
+        &self.path
     }
 }
 
-impl<S> PreviewTarget<DualPulsePumpLleController, S> for BasicPreviewTarget
+impl<C, S> PreviewTarget<C, S> for ParamTarget
 where
+    C: Controller<S> + Clone,
     S: Simulator,
-    DualPulsePumpLleController: Controller<S>,
 {
-    fn sync(
-        &self,
-        value: f64,
-        src: &DualPulsePumpLleController,
-        dst: &mut DualPulsePumpLleController,
-    ) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut DualPulsePumpLleController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => {
-                *controller.pump.pulse1.peak.value_mut() += value;
-                *controller.pump.pulse2.peak.value_mut() += value
+    fn apply(&self, value: f64, controller: &mut C) {
+        for path in self.resolve_all::<C, S>(controller) {
+            if let Some(v) = controller.get_param(&path) {
+                controller.set_param(&path, v + value);
             }
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
         }
     }
-}
 
-impl<S> PreviewTarget<InterleaveSelfPumpLleController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    InterleaveSelfPumpLleController: Controller<S>,
-{
-    fn sync(
-        &self,
-        value: f64,
-        src: &InterleaveSelfPumpLleController,
-        dst: &mut InterleaveSelfPumpLleController,
-    ) {
+    fn sync(&self, value: f64, src: &C, dst: &mut C) {
         *dst = src.clone();
         self.apply(value, dst);
     }
-    fn apply(&self, value: f64, controller: &mut InterleaveSelfPumpLleController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.loop_loss.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
+
+    fn value(&self, controller: &mut C) -> Option<f64> {
+        let path = self.resolve_all::<C, S>(controller).into_iter().next()?;
+        controller.get_param(&path)
+    }
+
+    fn driven(&self, controller: &mut C) -> Option<String> {
+        self.resolve_all::<C, S>(controller)
+            .iter()
+            .find_map(|path| controller.driven_by(path))
+    }
+
+    fn show_target(&mut self, ui: &mut egui::Ui, paths: &[String]) {
+        // shown resolved, but kept as saved until another parameter is picked,
+        // so drawing it doesn't change the config and rebuild the previews
+        let resolved = match self.legacy {
+            Some(l) => C::legacy_paths(l)
+                .iter()
+                .filter_map(|p| resolve(p, paths))
+                .collect::<Vec<_>>(),
+            None => self.resolve(paths).into_iter().collect(),
+        };
+        let selected = if resolved.is_empty() {
+            self.path.clone()
+        } else {
+            resolved
+                .iter()
+                .map(|p| p.as_str())
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        let mut picked = None;
+        egui::ComboBox::from_id_salt("target")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for p in paths {
+                    if ui.selectable_label(resolved[..] == [p], p).clicked() {
+                        picked = Some(p);
+                    }
+                }
+            });
+        if let Some(p) = picked
+            && (self.legacy.is_some() || *p != self.path)
+        {
+            *self = Self::new(p.clone());
+        } else if resolved.is_empty() {
+            ui.colored_label(ui.visuals().warn_fg_color, "Unknown parameter");
         }
     }
 }
 
-impl<S> PreviewTarget<GenCprtController, S> for BasicPreviewTarget
-where
-    S: Simulator,
-    GenCprtController: Controller<S>,
-{
-    fn sync(&self, value: f64, src: &GenCprtController, dst: &mut GenCprtController) {
-        *dst = src.clone();
-        self.apply(value, dst);
-    }
-    fn apply(&self, value: f64, controller: &mut GenCprtController) {
-        match self {
-            BasicPreviewTarget::Alpha => *controller.alpha.value_mut() += value,
-            BasicPreviewTarget::Pump => *controller.pump.amplitude.value_mut() += value,
-            BasicPreviewTarget::Linear => *controller.disper.linear.value_mut() += value,
-            BasicPreviewTarget::StepDist => *controller.step_dist.value_mut() += value,
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_paths() {
+        let paths = [
+            "alpha",
+            "pump.peak",
+            "pump.width",
+            "disper.linear",
+            "disper.period",
+        ]
+        .map(String::from);
+        let resolve = |p: &str| ParamTarget::new(p).resolve(&paths).map(String::as_str);
+        assert_eq!(resolve("alpha"), Some("alpha"));
+        assert_eq!(resolve("linear"), Some("disper.linear"));
+        assert_eq!(resolve("width"), Some("pump.width"));
+        assert_eq!(resolve(""), Some("alpha"));
+        assert_eq!(resolve("step_dist"), None);
+        // more than one parameter under it
+        assert_eq!(resolve("pump"), None);
+        assert_eq!(resolve("disper"), None);
+
+        let legacy: ParamTarget = ron::from_str("Pump").unwrap();
+        assert_eq!(legacy.legacy, Some(LegacyTarget::Pump));
+        let saved = ron::to_string(&legacy).unwrap();
+        assert_eq!(ron::from_str::<ParamTarget>(&saved).unwrap(), legacy);
+        let saved = ron::to_string(&ParamTarget::new("disper.period")).unwrap();
+        assert_eq!(
+            ron::from_str::<ParamTarget>(&saved).unwrap(),
+            ParamTarget::new("disper.period")
+        );
     }
 }
@@ -1,15 +1,21 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
 
 use egui::mutex::Mutex;
 use rayon::prelude::*;
 use refresh::Refresh;
 
-pub use impls::BasicPreviewTarget;
-use ui_traits::ControllerUI;
+pub use impls::{LegacyTarget, ParamTarget};
+use ui_traits::{ControllerUI, DisplayStr, Params};
 
 use crate::{
     app::Core,
-    controller::{Controller, SharedState, Simulator, StoreState},
+    controller::{Components, Controller, SharedState, Simulator, StoreState},
     util::{Promise, try_poll},
     views::{RawPlotData, ShowOn, State, Visualizer},
 };
@@ -34,12 +40,35 @@ where
     #[serde(skip)]
     pub(crate) sub_cores: Option<SubCores<Core<C, S>>>,
     pub(crate) refresh: Refresh,
+    /// push the main core to the checkpoints before adopting a preview
+    #[serde(default = "default_keep_main")]
+    pub(crate) keep_main: bool,
+    /// batch of steps of the previews running in the background
+    #[serde(skip)]
+    batch: Option<Batch<S>>,
+    /// bumped whenever the sub-cores or the offsets change, outdating the running batch
     #[serde(skip)]
-    promise: Option<Promise<Vec<<S as StoreState>::OwnedState>>>,
+    generation: u64,
     #[serde(skip)]
     cache: Option<Vec<<S as StoreState>::OwnedState>>,
 }
 
+/// A batch of steps of every sub-core
+struct Batch<S: StoreState> {
+    /// taken when the batch is over, `None` in it if cancelled
+    promise: Option<Promise<Option<Vec<S::OwnedState>>>>,
+    /// of the previews when the batch was started
+    generation: u64,
+    /// finished sub-cores
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    total: usize,
+}
+
+fn default_keep_main() -> bool {
+    true
+}
+
 impl<C, S, T> std::fmt::Debug for Previewer<C, S, T>
 where
     T: PreviewTarget<C, S> + Debug,
@@ -69,29 +98,115 @@ where
             config: self.config.clone(),
             sub_cores: None,
             refresh: self.refresh.clone(),
-            promise: None,
+            keep_main: self.keep_main,
+            batch: None,
+            generation: 0,
             cache: None,
         }
     }
 
-    pub fn show(&mut self, e: &Core<C, S>, ui: &mut egui::Ui) {
+    /// Returns the preview to adopt as the main core, if asked
+    pub fn show(&mut self, e: &Core<C, S>, ui: &mut egui::Ui) -> Option<Core<C, S>> {
         ui.collapsing("Parameter preview", |ui| {
             self.refresh.show(ui);
-            self.config.show(ui);
-            crate::util::show_option_with(ui, &mut self.sub_cores, "Previews", || {
-                self.config.refresh(e)
-            });
+            let paths = e.controller.clone().param_paths();
+            let config = self.config.clone();
+            self.config.show(ui, &paths);
+            let toggled =
+                crate::util::show_option_with(ui, &mut self.sub_cores, "Previews", || {
+                    self.config.refresh(e)
+                })
+                .changed();
+            if self.config != config {
+                self.invalidate();
+                if let Some(cores) = self.sub_cores.as_mut() {
+                    *cores = self.config.refresh(e);
+                }
+            } else if toggled {
+                self.invalidate();
+            }
 
-            if self.sub_cores.is_none() {
-                self.cache = None;
+            if let Some(batch) = &self.batch {
+                let done = batch.done.load(Ordering::Relaxed);
+                ui.add(
+                    egui::ProgressBar::new(done as f32 / batch.total.max(1) as f32)
+                        .text(format!("{done}/{} previews", batch.total))
+                        .desired_width(200.),
+                );
             }
 
             #[cfg(target_arch = "wasm32")]
             crate::util::warn_single_thread(ui);
-        });
+
+            self.show_previews(e, ui)
+        })
+        .body_returned
+        .flatten()
+    }
+
+    /// Effective values of the offset parameters of every preview, with an action to adopt it
+    fn show_previews(&mut self, e: &Core<C, S>, ui: &mut egui::Ui) -> Option<Core<C, S>> {
+        self.sub_cores.as_ref()?;
+        ui.checkbox(&mut self.keep_main, "Keep the main state")
+            .on_hover_text("Add the main state to the checkpoints before adopting a preview");
+        let mut adopt = None;
+        for (i, cell) in self.config.cells().into_iter().enumerate() {
+            let mut controller = e.controller.clone();
+            // a scheduled or linked target gets its offset overwritten on the next sync
+            let driven = cell.iter().find_map(|(t, _)| {
+                t.driven(&mut controller)
+                    .map(|by| format!("{}: {by}, its offset would be overwritten", t.desc()))
+            });
+            for (target, value) in &cell {
+                target.apply(*value, &mut controller);
+            }
+            let values = cell
+                .iter()
+                .map(|(t, _)| match t.value(&mut controller) {
+                    Some(v) => format!("{} = {v:.4}", t.desc()),
+                    None => format!("{} unknown", t.desc()),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            ui.horizontal(|ui| {
+                ui.label(format!("#{i}: {values}"));
+                let button = ui
+                    .add_enabled(driven.is_none(), egui::Button::new("⤴ Adopt").small())
+                    .on_hover_text("Make this preview the main simulation");
+                if let Some(driven) = &driven {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                        .on_hover_text(driven);
+                    button.on_disabled_hover_text(driven);
+                } else if button.clicked() {
+                    adopt = Some((i, controller));
+                }
+            });
+        }
+        let (i, controller) = adopt?;
+        // stop the batch before waiting for the sub-core it may be running
+        self.invalidate();
+        let sub = self.sub_cores.as_ref()?.cores.get(i)?.lock();
+        let mut simulator = controller.construct_engine(sub.dim);
+        simulator.set_owned_state(sub.simulator.get_owned_state());
+        let core = Core {
+            dim: sub.dim,
+            controller,
+            simulator,
+            random: e.random.clone(),
+            // the offsets were applied on the scheduled controller
+            automation: e.automation.clone(),
+            links: e.links.clone(),
+        };
+        drop(sub);
+        self.sub_cores = Some(self.config.refresh(&core));
+        Some(core)
     }
 
     pub fn plot_elements(&self) -> Option<Vec<RawPlotData<<S as StoreState>::OwnedState>>> {
+        // the grid is drawn in its own window
+        if self.config.layout == PreviewLayout::Grid {
+            return None;
+        }
         Some(
             self.states()?
                 .iter()
@@ -101,6 +216,7 @@ where
                     x: None,
                     width: state.1,
                     style: Some(Style::default()),
+                    legend: None,
                 })
                 .collect(),
         )
@@ -125,34 +241,109 @@ where
     }
 
     pub fn tick(&mut self, e: &Core<C, S>) {
-        if let Some(cores) = self.sub_cores.as_mut()
-            && self.refresh.tick()
-        {
-            *cores = self.config.refresh(e);
+        if self.sub_cores.is_some() && self.refresh.tick() {
+            self.invalidate();
+            self.sub_cores = Some(self.config.refresh(e));
+        }
+    }
+
+    /// Cancel the running batch and drop the previews, whose sub-cores or offsets changed
+    fn invalidate(&mut self) {
+        if let Some(batch) = self.batch.take() {
+            batch.cancel.store(true, Ordering::Relaxed);
         }
+        self.generation += 1;
+        self.cache = None;
     }
 
     pub fn poll_previews(&mut self, steps: u32, add_random: bool) -> Option<()> {
         puffin_egui::puffin::profile_function!();
-        let sub_cores = self.sub_cores.as_mut()?;
-        self.promise.get_or_insert_with(|| {
+        let sub_cores = self.sub_cores.as_ref()?;
+        let generation = self.generation;
+        let batch = self.batch.get_or_insert_with(|| {
+            let done = Arc::new(AtomicUsize::new(0));
+            let cancel = Arc::new(AtomicBool::new(false));
+            let task = sub_cores.run_sub_cores(steps, add_random, done.clone(), cancel.clone());
             #[cfg(not(target_arch = "wasm32"))]
-            {
-                Promise::new_thread("run_sub_cores", sub_cores.run_sub_cores(steps, add_random))
-            }
+            let promise = Promise::new_thread("run_sub_cores", task);
             #[cfg(target_arch = "wasm32")]
-            {
-                Promise::new_web("run_sub_cores", sub_cores.run_sub_cores(steps, add_random))
+            let promise = Promise::new_web("run_sub_cores", task);
+            Batch {
+                promise: Some(promise),
+                generation,
+                done,
+                cancel,
+                total: sub_cores.cores.len(),
             }
         });
-        if let Some(p) = try_poll(&mut self.promise) {
-            self.cache = Some(p);
+        if let Some(states) = try_poll(&mut batch.promise) {
+            let current = batch.generation == self.generation;
+            self.batch = None;
+            // a cancelled or outdated batch leaves the previews to the next one
+            if let Some(states) = states.filter(|_| current) {
+                self.cache = Some(states);
+            }
         }
         Some(())
     }
     pub fn states(&self) -> Option<&Vec<<S as StoreState>::OwnedState>> {
         self.cache.as_ref()
     }
+
+    /// Small multiples of the previews of the grid, laid out along its last axis
+    pub fn show_grid(&self, ctx: &egui::Context) {
+        let (Some(states), Some(last)) = (self.states(), self.config.grid.last()) else {
+            return;
+        };
+        if self.config.layout != PreviewLayout::Grid || self.sub_cores.is_none() {
+            return;
+        }
+        egui::Window::new("Parameter grid")
+            .vscroll(true)
+            .hscroll(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("preview grid").show(ui, |ui| {
+                    let cells = self.config.cells();
+                    for (i, (state, cell)) in states.iter().zip(&cells).enumerate() {
+                        ui.vertical(|ui| {
+                            let label = cell
+                                .iter()
+                                .map(|(t, v)| format!("{}{v:+.3}", t.desc()))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            ui.small(label);
+                            show_mini_plot(ui, i, state.clone());
+                        });
+                        if (i + 1) % last.count.max(1) == 0 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+    }
+}
+
+fn show_mini_plot<O: Components>(ui: &mut egui::Ui, id: usize, mut state: O) {
+    egui_plot::Plot::new(("preview grid cell", id))
+        .width(160.)
+        .height(90.)
+        .show_axes(false)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show(ui, |plot_ui| {
+            for (c, field) in state.components_mut().into_iter().enumerate() {
+                plot_ui.line(egui_plot::Line::new(
+                    format!("|ψ{c}|²"),
+                    field
+                        .iter()
+                        .enumerate()
+                        .map(|(j, x)| [j as f64, x.norm_sqr()])
+                        .collect::<Vec<_>>(),
+                ));
+            }
+        });
 }
 
 impl<C, S, T> Default for Previewer<C, S, T>
@@ -166,7 +357,9 @@ where
             config: Default::default(),
             sub_cores: None,
             refresh: Default::default(),
-            promise: None,
+            keep_main: true,
+            batch: None,
+            generation: 0,
             cache: None,
         }
     }
@@ -185,18 +378,22 @@ where
     S: Simulator,
     Core<C, S>: Send + Sync + 'static,
 {
+    /// Run every sub-core `steps` further, counting the finished ones in `done`.
+    /// Setting `cancel` stops the run at the next chunk of steps, returning `None`
     pub fn run_sub_cores(
         &self,
         steps: u32,
         add_random: bool,
-    ) -> impl FnOnce() -> Vec<<S as StoreState>::OwnedState> + Send + 'static
+        done: Arc<AtomicUsize>,
+        cancel: Arc<AtomicBool>,
+    ) -> impl FnOnce() -> Option<Vec<<S as StoreState>::OwnedState>> + Send + 'static
     where
         <S as StoreState>::OwnedState: Send + 'static,
     {
+        const CHUNKS: u32 = 8;
         let cores = self.cores.clone();
 
         move || {
-            let mut vec = Vec::new();
             cores
                 .par_iter()
                 .map(|c| {
@@ -205,20 +402,35 @@ where
                         c.add_random();
                     }
                     c.sync_paras();
-                    c.simulator.run(steps);
-                    c.simulator.get_owned_state()
+                    let chunk = steps.div_ceil(CHUNKS).max(1);
+                    let mut left = steps;
+                    while left > 0 {
+                        if cancel.load(Ordering::Relaxed) {
+                            return None;
+                        }
+                        let n = chunk.min(left);
+                        c.simulator.run(n);
+                        left -= n;
+                    }
+                    done.fetch_add(1, Ordering::Relaxed);
+                    Some(c.simulator.get_owned_state())
                 })
-                .collect_into_vec(&mut vec);
-            vec
+                .collect()
         }
     }
 }
 
 pub trait PreviewTarget<C: Controller<E>, E: Simulator>:
-    Send + Sync + ControllerUI + Default
+    Send + Sync + DisplayStr + Default + Clone + PartialEq
 {
     fn apply(&self, value: f64, controller: &mut C);
     fn sync(&self, value: f64, src: &C, dst: &mut C);
+    /// Current value of the target in `controller`
+    fn value(&self, controller: &mut C) -> Option<f64>;
+    /// What overwrites the target in `controller` on every sync, like a link or a schedule
+    fn driven(&self, controller: &mut C) -> Option<String>;
+    /// Pick the target among the parameter `paths` of the controller
+    fn show_target(&mut self, ui: &mut egui::Ui, paths: &[String]);
 }
 
 #[derive(
@@ -226,16 +438,90 @@ pub trait PreviewTarget<C: Controller<E>, E: Simulator>:
 )]
 pub struct Offset<T>(T, f64);
 
-impl<T: ControllerUI> ControllerUI for Offset<T> {
-    fn show_controller(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            self.0.show_controller(ui);
-            ui.add(egui::DragValue::new(&mut self.1).prefix("Δ = "));
-        });
+impl<T> Offset<T> {
+    fn show<C: Controller<S>, S: Simulator>(&mut self, ui: &mut egui::Ui, paths: &[String])
+    where
+        T: PreviewTarget<C, S>,
+    {
+        self.0.show_target(ui, paths);
+        ui.add(egui::DragValue::new(&mut self.1).prefix("Δ = "));
+    }
+}
+
+/// Offsets of one target along an axis of the grid, evenly spaced from `from` to `to`
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GridAxis<T> {
+    target: T,
+    from: f64,
+    to: f64,
+    count: usize,
+}
+
+impl<T: Default> Default for GridAxis<T> {
+    fn default() -> Self {
+        Self {
+            target: T::default(),
+            from: -1.,
+            to: 1.,
+            count: 3,
+        }
+    }
+}
+
+impl<T> GridAxis<T> {
+    fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        let step = if self.count > 1 {
+            (self.to - self.from) / (self.count - 1) as f64
+        } else {
+            0.
+        };
+        (0..self.count).map(move |i| self.from + step * i as f64)
+    }
+
+    fn show<C: Controller<S>, S: Simulator>(&mut self, ui: &mut egui::Ui, paths: &[String])
+    where
+        T: PreviewTarget<C, S>,
+    {
+        self.target.show_target(ui, paths);
+        ui.add(egui::DragValue::new(&mut self.from).prefix("Δ from "));
+        ui.add(egui::DragValue::new(&mut self.to).prefix("to "));
+        ui.add(
+            egui::DragValue::new(&mut self.count)
+                .range(1..=16)
+                .prefix("× "),
+        );
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    enum_iterator::Sequence,
+)]
+pub enum PreviewLayout {
+    /// drawn on the main charts
+    #[default]
+    Overlay,
+    /// Cartesian product of the axes, drawn as small multiples
+    Grid,
+}
+
+impl DisplayStr for PreviewLayout {
+    fn desc(&self) -> &str {
+        match self {
+            PreviewLayout::Overlay => "Overlay",
+            PreviewLayout::Grid => "Grid",
+        }
     }
 }
 
-#[derive(PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 #[serde(bound(
     serialize = "T: serde::Serialize",
     deserialize = "T: for<'a> serde::Deserialize<'a>"
@@ -247,6 +533,10 @@ where
     S: Simulator,
 {
     offsets: Vec<Offset<T>>,
+    #[serde(default)]
+    layout: PreviewLayout,
+    #[serde(default)]
+    grid: Vec<GridAxis<T>>,
     phantom: std::marker::PhantomData<Core<C, S>>,
 }
 
@@ -259,6 +549,8 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Config")
             .field("offsets", &self.offsets)
+            .field("layout", &self.layout)
+            .field("grid", &self.grid)
             .finish()
     }
 }
@@ -272,11 +564,24 @@ where
     fn clone(&self) -> Self {
         Self {
             offsets: self.offsets.clone(),
+            layout: self.layout,
+            grid: self.grid.clone(),
             phantom: std::marker::PhantomData,
         }
     }
 }
 
+impl<T, C, S> PartialEq for PreviewConfig<T, C, S>
+where
+    T: PreviewTarget<C, S>,
+    C: Controller<S>,
+    S: Simulator,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.offsets == other.offsets && self.layout == other.layout && self.grid == other.grid
+    }
+}
+
 impl<T, C, S> Default for PreviewConfig<T, C, S>
 where
     T: PreviewTarget<C, S> + Default,
@@ -286,6 +591,8 @@ where
     fn default() -> Self {
         Self {
             offsets: Vec::new(),
+            layout: PreviewLayout::default(),
+            grid: Vec::new(),
             phantom: std::marker::PhantomData,
         }
     }
@@ -297,19 +604,64 @@ where
     S: Simulator + Send + Sync,
     C: Controller<S> + Clone + Send + Sync,
 {
-    pub fn show(&mut self, ui: &mut egui::Ui) {
-        crate::util::show_vector(ui, &mut self.offsets);
+    /// With the parameter `paths` of the controller to offset
+    pub fn show(&mut self, ui: &mut egui::Ui, paths: &[String]) {
+        ui.horizontal(|ui| {
+            ui.label("Layout");
+            egui::ComboBox::from_id_salt("preview layout")
+                .selected_text(self.layout.desc())
+                .show_ui(ui, |ui| self.layout.show_controller(ui));
+        });
+        match self.layout {
+            PreviewLayout::Overlay => {
+                crate::util::show_vector_with(ui, &mut self.offsets, |ui, o| {
+                    o.show::<C, S>(ui, paths)
+                })
+            }
+            PreviewLayout::Grid => {
+                crate::util::show_vector_with(ui, &mut self.grid, |ui, a| {
+                    a.show::<C, S>(ui, paths)
+                });
+                ui.label(format!("{} previews", self.cells().len()));
+            }
+        }
+    }
+
+    /// Offsets of every preview, applied in order
+    fn cells(&self) -> Vec<Vec<(&T, f64)>> {
+        match self.layout {
+            PreviewLayout::Overlay => self
+                .offsets
+                .iter()
+                .map(|Offset(target, value)| vec![(target, *value)])
+                .collect(),
+            PreviewLayout::Grid if self.grid.is_empty() => Vec::new(),
+            PreviewLayout::Grid => self.grid.iter().fold(vec![Vec::new()], |cells, axis| {
+                cells
+                    .into_iter()
+                    .flat_map(|cell| {
+                        axis.values().map(move |value| {
+                            let mut cell = cell.clone();
+                            cell.push((&axis.target, value));
+                            cell
+                        })
+                    })
+                    .collect()
+            }),
+        }
     }
 
     pub fn refresh(&mut self, e: &Core<C, S>) -> SubCores<Core<C, S>> {
         puffin_egui::puffin::profile_function!();
         let mut ret = Vec::new();
-        for Offset(target, value) in &self.offsets {
+        for cell in self.cells() {
             let mut c = e.controller.clone();
             let state = e.simulator.get_owned_state();
             let dim = e.dim;
             let r = e.random.clone();
-            target.apply(*value, &mut c);
+            for (target, value) in cell {
+                target.apply(value, &mut c);
+            }
             let mut s: S = c.construct_engine(dim);
             s.set_owned_state(state);
             let core = Core {
@@ -317,6 +669,10 @@ where
                 controller: c,
                 simulator: s,
                 random: r,
+                // the offsets are applied on the already scheduled controller of `e`
+                automation: Default::default(),
+                // while the linked parameters follow the offsets
+                links: e.links.clone(),
             };
             ret.push(Mutex::new(core));
         }
@@ -327,13 +683,82 @@ where
 
     pub fn sync(&mut self, src: &Core<C, S>, dst: &mut SubCores<Core<C, S>>) {
         puffin_egui::puffin::profile_function!();
-        self.offsets.par_iter().zip(dst.cores.par_iter()).for_each(
-            |(Offset(target, value), core)| {
+        self.cells()
+            .par_iter()
+            .zip(dst.cores.par_iter())
+            .for_each(|(cell, core)| {
                 let mut core = core.lock();
                 let c = &mut core.controller;
-                target.sync(*value, &src.controller, c);
+                if let Some(((target, value), rest)) = cell.split_first() {
+                    target.sync(*value, &src.controller, c);
+                    for (target, value) in rest {
+                        target.apply(*value, c);
+                    }
+                }
                 core.random = src.random.clone();
-            },
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lle::{NoneOp, SPhaMod, num_complex::Complex64};
+
+    use super::*;
+    use crate::controller::{LleController, LleSolver};
+
+    type Config =
+        PreviewConfig<ParamTarget, LleController, LleSolver<SPhaMod, Complex64, NoneOp<f64>>>;
+
+    #[test]
+    fn grid_cells() {
+        let (alpha, pump) = (ParamTarget::new("alpha"), ParamTarget::new("pump"));
+        let config = Config {
+            layout: PreviewLayout::Grid,
+            grid: vec![
+                GridAxis {
+                    target: alpha.clone(),
+                    from: -1.,
+                    to: 1.,
+                    count: 3,
+                },
+                GridAxis {
+                    target: pump.clone(),
+                    from: 0.,
+                    to: 0.5,
+                    count: 2,
+                },
+            ],
+            ..Default::default()
+        };
+        let cells = config.cells();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[1], vec![(&alpha, -1.), (&pump, 0.5)]);
+        assert_eq!(cells[4], vec![(&alpha, 1.), (&pump, 0.)]);
+    }
+
+    #[test]
+    fn cancel_batch() {
+        let core = Core::new(LleController::default(), 64);
+        let mut config = Config {
+            offsets: vec![
+                Offset(ParamTarget::new("alpha"), 1.),
+                Offset(ParamTarget::new("pump"), 0.5),
+            ],
+            ..Default::default()
+        };
+        let sub_cores = config.refresh(&core);
+
+        let (done, cancel) = (
+            Arc::new(AtomicUsize::new(0)),
+            Arc::new(AtomicBool::new(false)),
         );
+        let states = sub_cores.run_sub_cores(10, false, done.clone(), cancel.clone())();
+        assert_eq!(states.map(|s| s.len()), Some(2));
+        assert_eq!(done.load(Ordering::Relaxed), 2);
+
+        cancel.store(true, Ordering::Relaxed);
+        assert!(sub_cores.run_sub_cores(10, false, done.clone(), cancel)().is_none());
+        assert_eq!(done.load(Ordering::Relaxed), 2);
     }
 }
@@ -32,6 +32,9 @@ pub(crate) struct Property<T: Num> {
     pub(crate) value_suffix: Option<String>,
     #[serde(default)]
     pub(crate) on_hover_text: Option<String>,
+    /// Set when the value is written every frame by a schedule, instead of the widget
+    #[serde(skip)]
+    pub(crate) driven: Option<String>,
 }
 
 fn custom_drag<T: egui::emath::Numeric + std::str::FromStr>(
@@ -63,6 +66,7 @@ impl<T: Num + Copy> Property<T> {
             show_editor: Some(false),
             value_suffix: None,
             on_hover_text: None,
+            driven: None,
         }
     }
     pub fn new_no_slider(v: T, label: impl ToString) -> Self {
@@ -73,6 +77,7 @@ impl<T: Num + Copy> Property<T> {
             show_editor: None,
             value_suffix: None,
             on_hover_text: None,
+            driven: None,
         }
     }
     pub fn symbol(mut self, symbol: impl ToString) -> Self {
@@ -131,6 +136,10 @@ impl<T: Num + FromStr> Property<T> {
         self.value.show(ui, label, suffix.as_deref());
     } */
     pub(crate) fn show_as_drag_value(&mut self, ui: &mut egui::Ui) {
+        if let Some(by) = self.driven.clone() {
+            ui.add_enabled_ui(false, |ui| self.show_driven(ui, by));
+            return;
+        }
         //ui.horizontal_wrapped(|ui| {
         let label = self.symbol.as_deref().unwrap_or(self.label.as_str());
         // let suffix = self.value_suffix.clone();
@@ -146,6 +155,12 @@ impl<T: Num + FromStr> Property<T> {
         self.show_as_drag_value(ui);
     }
     pub(crate) fn show_in_control_panel(&mut self, ui: &mut egui::Ui) {
+        if let Some(by) = self.driven.clone() {
+            ui.horizontal_wrapped(|ui| {
+                ui.add_enabled_ui(false, |ui| self.show_driven(ui, by));
+            });
+            return;
+        }
         if self.show_editor.is_none() {
             ui.horizontal_wrapped(|ui| {
                 self.show_in_builder(ui);
@@ -178,9 +193,42 @@ impl<T: Num + FromStr> Property<T> {
     }
 }
 
+impl<T: Num + FromStr> Property<T> {
+    /// Read only value of a driven property
+    fn show_driven(&mut self, ui: &mut egui::Ui, by: String) {
+        let label = self.symbol.as_deref().unwrap_or(self.label.as_str());
+        self.value
+            .show(ui, label, self.value_suffix.as_deref(), None);
+        ui.label("⛓").on_disabled_hover_text(by);
+    }
+}
+
 impl<T: Num> Property<T> {
     pub fn on_hover_text(mut self, text: impl ToString) -> Self {
         self.on_hover_text = text.to_string().into();
         self
     }
 }
+
+impl<T: Num> ui_traits::Param for Property<T> {
+    fn get_f64(&self) -> f64 {
+        eframe::emath::Numeric::to_f64(self.value.value)
+    }
+    fn set_f64(&mut self, value: f64) {
+        if let Some(v) = num_traits::FromPrimitive::from_f64(value) {
+            self.value.value = v;
+        }
+    }
+    fn set_driven(&mut self, by: Option<String>) {
+        self.driven = by;
+    }
+    fn driven(&self) -> Option<&str> {
+        self.driven.as_deref()
+    }
+}
+
+impl<T: Num> ui_traits::Params for Property<T> {
+    fn visit_params(&mut self, prefix: &str, f: &mut dyn FnMut(&str, &mut dyn ui_traits::Param)) {
+        f(prefix, self)
+    }
+}
@@ -3,9 +3,13 @@ use std::f64::consts::PI;
 use lle::num_complex::Complex64;
 use static_assertions::assert_impl_all;
 
+/// `StdRng` is a `ChaCha12Rng`, used directly for its seekable stream
 #[derive(Debug, Clone)]
 enum RandCore {
-    Seed { seed: u64, rng: rand::rngs::StdRng },
+    Seed {
+        seed: u64,
+        rng: rand_chacha::ChaCha12Rng,
+    },
     //Thread(rand::rngs::ThreadRng),
 }
 
@@ -18,7 +22,7 @@ impl RandCore {
 
         Self::Seed {
             seed,
-            rng: rand::rngs::StdRng::seed_from_u64(seed),
+            rng: rand_chacha::ChaCha12Rng::seed_from_u64(seed),
         }
     }
 }
@@ -53,12 +57,19 @@ impl<'a> serde::Deserialize<'a> for RandCore {
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct RandCoreStorage {
     seed: Option<u64>,
+    /// position in the stream of the seed, in 32-bit words,
+    /// so a restored generator goes on with the same numbers
+    #[serde(default)]
+    word_pos: u64,
 }
 
 impl From<RandCore> for RandCoreStorage {
     fn from(core: RandCore) -> Self {
         match core {
-            RandCore::Seed { seed, .. } => Self { seed: Some(seed) },
+            RandCore::Seed { seed, rng } => Self {
+                seed: Some(seed),
+                word_pos: rng.get_word_pos() as u64,
+            },
             //RandCore::Thread(_) => Self { seed: None },
         }
     }
@@ -66,7 +77,11 @@ impl From<RandCore> for RandCoreStorage {
 
 impl From<RandCoreStorage> for RandCore {
     fn from(storage: RandCoreStorage) -> Self {
-        Self::new(storage.seed)
+        let mut core = Self::new(storage.seed);
+        match &mut core {
+            RandCore::Seed { rng, .. } => rng.set_word_pos(storage.word_pos as u128),
+        }
+        core
     }
 }
 
@@ -100,6 +115,14 @@ impl RandomNoise {
         }
     }
 
+    pub(crate) fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+
+    pub(crate) fn set_std_dev(&mut self, std_dev: f64) {
+        self.std_dev = std_dev;
+    }
+
     pub(crate) fn show(&mut self, ui: &mut egui::Ui, add: &mut bool) {
         ui.horizontal(|ui| {
             ui.toggle_value(add, "Noise");
@@ -19,16 +19,27 @@ pub(crate) fn show_dim(ui: &mut egui::Ui, dim: &mut usize) {
 }
 
 pub(crate) fn show_vector<V: ui_traits::ControllerUI + Default>(ui: &mut egui::Ui, v: &mut Vec<V>) {
+    show_vector_with(ui, v, |ui, value| value.show_controller(ui))
+}
+
+/// [`show_vector`] with the elements shown by `show`
+pub(crate) fn show_vector_with<V: Default>(
+    ui: &mut egui::Ui,
+    v: &mut Vec<V>,
+    mut show: impl FnMut(&mut egui::Ui, &mut V),
+) {
     ui.vertical(|ui| {
         let mut to_remove = None;
         for (i, value) in v.iter_mut().enumerate() {
-            ui.horizontal(|ui| {
-                ui.add_space(4.0);
-                value.show_controller(ui);
-                ui.add_space(4.0);
-                if ui.button("🗑").clicked() {
-                    to_remove = Some(i);
-                }
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_space(4.0);
+                    show(ui, value);
+                    ui.add_space(4.0);
+                    if ui.button("🗑").clicked() {
+                        to_remove = Some(i);
+                    }
+                })
             });
         }
 
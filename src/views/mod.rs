@@ -102,6 +102,8 @@ pub struct RawPlotData<S> {
     pub(crate) width: Width,
     #[serde(default)]
     pub(crate) style: Option<crate::drawer::plot_item::Style>,
+    #[serde(default)]
+    pub(crate) legend: Option<String>,
 }
 
 impl<S, const L: usize> RawPlotData<[S; L]> {
@@ -111,12 +113,14 @@ impl<S, const L: usize> RawPlotData<[S; L]> {
             x,
             width,
             style,
+            legend,
         } = self;
         data.map(|d| RawPlotData {
             data: d,
             x: x.clone(),
             width,
             style,
+            legend: legend.clone(),
         })
     }
 }
@@ -21,3 +21,71 @@ impl<T: DisplayStr + enum_iterator::Sequence + Eq> ControllerUI for T {
         })
     }
 }
+
+/// A single numeric parameter, seen as a `f64`
+pub trait Param {
+    fn get_f64(&self) -> f64;
+    fn set_f64(&mut self, value: f64);
+    /// Mark the parameter as set by something else than its widget, `by` being shown as the reason
+    fn set_driven(&mut self, by: Option<String>);
+    /// What sets the parameter, if not its widget
+    fn driven(&self) -> Option<&str>;
+}
+
+/// Numeric parameters reachable by their dot separated path, like `disper.center_pos`
+pub trait Params {
+    /// Call `f` with the path and the parameter of every numeric field, prefixing the paths with `prefix`
+    fn visit_params(&mut self, prefix: &str, f: &mut dyn FnMut(&str, &mut dyn Param));
+
+    fn param_paths(&mut self) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.visit_params("", &mut |path, _| paths.push(path.to_string()));
+        paths
+    }
+
+    fn get_param(&mut self, path: &str) -> Option<f64> {
+        let mut value = None;
+        self.visit_params("", &mut |p, param| {
+            if p == path {
+                value = Some(param.get_f64());
+            }
+        });
+        value
+    }
+
+    /// Returns `false` if there is no parameter at `path`
+    fn set_param(&mut self, path: &str, value: f64) -> bool {
+        let mut found = false;
+        self.visit_params("", &mut |p, param| {
+            if p == path {
+                param.set_f64(value);
+                found = true;
+            }
+        });
+        found
+    }
+
+    /// What sets the parameter at `path`, see [`Param::set_driven`]
+    fn driven_by(&mut self, path: &str) -> Option<String> {
+        let mut by = None;
+        self.visit_params("", &mut |p, param| {
+            if p == path {
+                by = param.driven().map(str::to_string);
+            }
+        });
+        by
+    }
+
+    fn clear_driven(&mut self) {
+        self.visit_params("", &mut |_, param| param.set_driven(None));
+    }
+}
+
+/// Path of the field `name` of the struct at `prefix`
+pub fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
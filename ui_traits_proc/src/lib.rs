@@ -27,6 +27,73 @@ pub fn derive_show_controller_ui(input: TokenStream) -> TokenStream {
     simple_derive(&input, &trait_item, Some(&path)).into()
 }
 
+/// Implement `Params` by visiting every named field, as a parameter or a group of them
+/// at the path of its name. Fields marked `#[params(skip)]` are left out
+#[proc_macro_derive(Params, attributes(params))]
+pub fn derive_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let path: syn::Path = syn::parse_quote! {::ui_traits};
+    params_derive(&input, &path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn params_derive(
+    derive: &DeriveInput,
+    trait_path: &syn::Path,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data_struct) = &derive.data else {
+        return Err(syn::Error::new_spanned(
+            derive,
+            "Params can only be derived for structs",
+        ));
+    };
+    let mut visits = Vec::new();
+    for field in &data_struct.fields {
+        let Some(name) = field.ident.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                field,
+                "Params can only be derived for named fields",
+            ));
+        };
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("params")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        let key = name.to_string();
+        visits.push(quote_spanned! {field.ty.span()=>
+            #trait_path::Params::visit_params(
+                &mut self.#name,
+                &#trait_path::join_path(prefix, #key),
+                f,
+            );
+        });
+    }
+    let target_name = &derive.ident;
+    let (impl_generics, ty_generics, where_clause) = derive.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #trait_path::Params for #target_name #ty_generics #where_clause {
+            fn visit_params(
+                &mut self,
+                prefix: &str,
+                f: &mut dyn FnMut(&str, &mut dyn #trait_path::Param),
+            ) {
+                #(#visits)*
+            }
+        }
+    })
+}
+
 fn simple_derive(
     derive: &DeriveInput,
     trait_item: &syn::ItemTrait,
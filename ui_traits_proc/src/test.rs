@@ -61,3 +61,49 @@ fn test_simple_derive_enum() {
         .to_string()
     );
 }
+
+#[test]
+fn test_params_derive() {
+    let derive = syn::parse_quote! {
+        struct TestStruct {
+            alpha: Property<f64>,
+            #[params(skip)]
+            cache: Vec<f64>,
+            pump: SubController,
+        }
+    };
+    let path: syn::Path = syn::parse_quote! {::ui_traits};
+
+    let result = params_derive(&derive, &path).unwrap();
+    assert_eq!(
+        result.to_string(),
+        quote! {
+            impl ::ui_traits::Params for TestStruct {
+                fn visit_params(
+                    &mut self,
+                    prefix: &str,
+                    f: &mut dyn FnMut(&str, &mut dyn ::ui_traits::Param),
+                ) {
+                    ::ui_traits::Params::visit_params(
+                        &mut self.alpha,
+                        &::ui_traits::join_path(prefix, "alpha"),
+                        f,
+                    );
+                    ::ui_traits::Params::visit_params(
+                        &mut self.pump,
+                        &::ui_traits::join_path(prefix, "pump"),
+                        f,
+                    );
+                }
+            }
+        }
+        .to_string()
+    );
+
+    let derive = syn::parse_quote! {
+        enum TestEnum {
+            A(i32),
+        }
+    };
+    assert!(params_derive(&derive, &path).is_err());
+}